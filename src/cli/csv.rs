@@ -1,28 +1,1206 @@
-use super::{output_format_parse, verity_input_file, OutputFormat};
-use crate::{process_csv, CmdExector};
-use clap::Parser;
+use super::{output_format_parse, verity_dir_exist, verity_input_file, OutputFormat};
+use crate::{
+    process_csv, process_csv_concat, process_csv_decrypt, process_csv_diff, process_csv_encrypt,
+    process_csv_fake, process_csv_groupby, process_csv_index, process_csv_join, process_csv_lint,
+    process_csv_mask, process_csv_pivot, process_csv_query, process_csv_reverse,
+    process_csv_sample, process_csv_schema, process_csv_split, process_csv_stats,
+    process_csv_validate, process_csv_view, CmdExector,
+};
+use chrono::NaiveDate;
+use clap::{ArgAction, Args, Parser};
+use encoding_rs::Encoding;
+use enum_dispatch::enum_dispatch;
+use std::fmt;
+use std::path::PathBuf;
+use std::str::FromStr;
 
 #[derive(Debug, Parser)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct CsvOpts {
-    #[arg(short, long, default_value_t = ',')]
-    pub delimiter: char,
-    #[arg(short, long, value_parser = verity_input_file)]
+    #[command(subcommand)]
+    pub cmd: Option<Box<CsvSubCommand>>,
+    #[arg(short, long, default_value = "-", value_parser = verity_input_file)]
     pub input: String,
-    #[arg(long, default_value_t = true)]
-    pub header: bool,
+    #[command(flatten)]
+    pub read: CsvReadOpts,
     #[arg(short, long)]
     pub output: Option<String>,
     #[arg(short, long, default_value = "json", value_parser = output_format_parse)]
     pub format: OutputFormat,
+    #[command(flatten)]
+    pub infer: CsvInferOpts,
+    #[arg(long)]
+    pub reverse: bool,
+    #[arg(long, default_value = ";")]
+    pub separator: String,
+    #[command(flatten)]
+    pub write: CsvWriteOpts,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct CsvReadOpts {
+    #[arg(short, long, default_value = ",", value_parser = parse_delimiter)]
+    pub delimiter: u8,
+    #[arg(long, default_value_t = true, action = ArgAction::Set)]
+    pub header: bool,
+    #[arg(long)]
+    pub sniff: bool,
+    #[arg(long, value_parser = parse_encoding)]
+    pub encoding: Option<&'static Encoding>,
+    #[arg(long)]
+    pub sheet: Option<String>,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct CsvInferOpts {
+    #[arg(long)]
+    pub infer: bool,
+    #[arg(long, default_value_t = 100)]
+    pub sample: usize,
+    #[arg(long, value_parser = verity_input_file)]
+    pub types: Option<String>,
+}
+
+#[derive(Debug, Clone, Args)]
+pub struct CsvWriteOpts {
+    #[arg(long)]
+    pub toml_root: Option<String>,
+    #[arg(long)]
+    pub table: Option<String>,
+    #[arg(long, default_value = "sqlite", value_parser = verify_sql_dialect)]
+    pub dialect: SqlDialect,
+    #[arg(long, default_value_t = 500)]
+    pub batch_size: usize,
+    #[arg(long, default_value = "snappy", value_parser = verify_parquet_compression)]
+    pub compression: ParquetCompression,
+}
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExector)]
+pub enum CsvSubCommand {
+    #[command(about = "Filter, project, sort and limit CSV rows")]
+    Query(CsvQueryOpts),
+    #[command(about = "Profile CSV columns")]
+    Stats(CsvStatsOpts),
+    #[command(about = "Compare two CSV files by key columns")]
+    Diff(CsvDiffOpts),
+    #[command(about = "Join two CSV files on key columns")]
+    Join(CsvJoinOpts),
+    #[command(about = "Concatenate CSV files, merging their headers")]
+    Concat(CsvConcatOpts),
+    #[command(about = "Show CSV as an aligned table")]
+    View(CsvViewOpts),
+    #[command(about = "Validate CSV against a schema file")]
+    Validate(CsvValidateOpts),
+    #[command(about = "Infer a JSON Schema or CREATE TABLE statement from CSV")]
+    Schema(CsvSchemaOpts),
+    #[command(about = "Pseudonymize CSV columns with a keyed BLAKE3 hash")]
+    Mask(CsvMaskOpts),
+    #[command(about = "Encrypt selected CSV columns with ChaCha20-Poly1305")]
+    Encrypt(CsvEncryptOpts),
+    #[command(about = "Decrypt selected CSV columns with ChaCha20-Poly1305")]
+    Decrypt(CsvDecryptOpts),
+    #[command(about = "Report structural problems in CSV files")]
+    Lint(CsvLintOpts),
+    #[command(name = "groupby", about = "Aggregate CSV rows grouped by key columns")]
+    GroupBy(CsvGroupByOpts),
+    #[command(about = "Turn the values of a CSV column into columns")]
+    Pivot(CsvPivotOpts),
+    #[command(about = "Randomly sample CSV rows in a single pass")]
+    Sample(CsvSampleOpts),
+    #[command(about = "Split CSV into files by row count or byte size")]
+    Split(CsvSplitOpts),
+    #[command(about = "Build a row offset index for fast slicing of large CSV files")]
+    Index(CsvIndexOpts),
+    #[command(about = "Generate synthetic CSV rows from a column spec")]
+    Fake(CsvFakeOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvQueryOpts {
+    #[arg(short, long, default_value = "-", value_parser = verity_input_file)]
+    pub input: String,
+    #[command(flatten)]
+    pub read: CsvReadOpts,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    #[arg(short, long, default_value = "csv", value_parser = output_format_parse)]
+    pub format: OutputFormat,
+    pub query: String,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvStatsOpts {
+    #[arg(short, long, default_value = "-", value_parser = verity_input_file)]
+    pub input: String,
+    #[command(flatten)]
+    pub read: CsvReadOpts,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    #[arg(short, long, value_parser = output_format_parse)]
+    pub format: Option<OutputFormat>,
+    #[arg(long, default_value_t = 5)]
+    pub top: usize,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvDiffOpts {
+    #[arg(value_parser = verity_input_file)]
+    pub old: String,
+    #[arg(value_parser = verity_input_file)]
+    pub new: String,
+    #[arg(short, long, required = true, value_delimiter = ',')]
+    pub key: Vec<String>,
+    #[command(flatten)]
+    pub read: CsvReadOpts,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    #[arg(short, long, value_parser = output_format_parse)]
+    pub format: Option<OutputFormat>,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvJoinOpts {
+    #[arg(value_parser = verity_input_file)]
+    pub left: String,
+    #[arg(value_parser = verity_input_file)]
+    pub right: String,
+    #[arg(short, long, required = true, value_delimiter = ',')]
+    pub key: Vec<String>,
+    #[arg(long, default_value = "inner", value_parser = verify_join_kind)]
+    pub how: JoinKind,
+    #[command(flatten)]
+    pub read: CsvReadOpts,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    #[arg(short, long, default_value = "csv", value_parser = output_format_parse)]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvConcatOpts {
+    #[arg(required = true, value_parser = verity_input_file)]
+    pub inputs: Vec<String>,
+    #[command(flatten)]
+    pub read: CsvReadOpts,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    #[arg(short, long, default_value = "csv", value_parser = output_format_parse)]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvViewOpts {
+    #[arg(short, long, default_value = "-", value_parser = verity_input_file)]
+    pub input: String,
+    #[command(flatten)]
+    pub read: CsvReadOpts,
+    #[arg(long, conflicts_with_all = ["tail", "range"])]
+    pub head: Option<usize>,
+    #[arg(long, conflicts_with = "range")]
+    pub tail: Option<usize>,
+    #[arg(long, value_parser = parse_row_range)]
+    pub range: Option<(usize, usize)>,
+    #[arg(long, default_value_t = 50)]
+    pub page_size: usize,
+    #[arg(long)]
+    pub width: Option<usize>,
+    #[arg(long, default_value_t = 40)]
+    pub max_cell_width: usize,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvValidateOpts {
+    #[arg(short, long, default_value = "-", value_parser = verity_input_file)]
+    pub input: String,
+    #[command(flatten)]
+    pub read: CsvReadOpts,
+    #[arg(short, long, value_parser = verity_input_file)]
+    pub schema: String,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    #[arg(short, long, value_parser = output_format_parse)]
+    pub format: Option<OutputFormat>,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvLintOpts {
+    #[arg(short, long, default_value = "-", value_parser = verity_input_file)]
+    pub input: String,
+    #[command(flatten)]
+    pub read: CsvReadOpts,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    #[arg(short, long, value_parser = output_format_parse)]
+    pub format: Option<OutputFormat>,
+    #[arg(long)]
+    pub fix: Option<String>,
+    #[arg(long, default_value = ",", value_parser = parse_delimiter)]
+    pub fix_delimiter: u8,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvGroupByOpts {
+    #[arg(short, long, default_value = "-", value_parser = verity_input_file)]
+    pub input: String,
+    #[command(flatten)]
+    pub read: CsvReadOpts,
+    #[arg(short, long, required = true, value_delimiter = ',')]
+    pub key: Vec<String>,
+    #[arg(short, long = "agg", default_value = "count", value_parser = parse_aggregation)]
+    pub aggs: Vec<Aggregation>,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    #[arg(short, long, default_value = "csv", value_parser = output_format_parse)]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvPivotOpts {
+    #[arg(short, long, default_value = "-", value_parser = verity_input_file)]
+    pub input: String,
+    #[command(flatten)]
+    pub read: CsvReadOpts,
+    #[arg(short, long, required = true, value_delimiter = ',')]
+    pub key: Vec<String>,
+    #[arg(short, long)]
+    pub column: String,
+    #[arg(short, long, default_value = "count", value_parser = parse_aggregation)]
+    pub agg: Aggregation,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    #[arg(short, long, default_value = "csv", value_parser = output_format_parse)]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvSampleOpts {
+    #[arg(short, long, default_value = "-", value_parser = verity_input_file)]
+    pub input: String,
+    #[command(flatten)]
+    pub read: CsvReadOpts,
+    #[arg(short = 'n', long)]
+    pub size: usize,
+    #[arg(long)]
+    pub seed: Option<u64>,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    #[arg(short, long, default_value = "csv", value_parser = output_format_parse)]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvSplitOpts {
+    #[arg(short, long, default_value = "-", value_parser = verity_input_file)]
+    pub input: String,
+    #[command(flatten)]
+    pub read: CsvReadOpts,
+    #[arg(long, required_unless_present = "bytes", conflicts_with = "bytes", value_parser = clap::value_parser!(u64).range(1..))]
+    pub rows: Option<u64>,
+    #[arg(long, value_parser = parse_size)]
+    pub bytes: Option<u64>,
+    #[arg(long, default_value = ".", value_parser = verity_dir_exist)]
+    pub dir: PathBuf,
+    #[arg(long)]
+    pub prefix: Option<String>,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvIndexOpts {
+    #[arg(short, long, value_parser = verity_input_file)]
+    pub input: String,
+    #[command(flatten)]
+    pub read: CsvReadOpts,
+    #[arg(long, default_value_t = 1000, value_parser = clap::value_parser!(u64).range(1..))]
+    pub every: u64,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvFakeOpts {
+    #[arg(short, long = "column", required = true, value_parser = parse_fake_column)]
+    pub columns: Vec<FakeColumn>,
+    #[arg(short = 'n', long, default_value_t = 10)]
+    pub rows: usize,
+    #[arg(long)]
+    pub seed: Option<u64>,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    #[arg(short, long, default_value = "csv", value_parser = output_format_parse)]
+    pub format: OutputFormat,
+    #[command(flatten)]
+    pub write: CsvWriteOpts,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvSchemaOpts {
+    #[arg(short, long, default_value = "-", value_parser = verity_input_file)]
+    pub input: String,
+    #[command(flatten)]
+    pub read: CsvReadOpts,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    #[arg(short, long, default_value = "json-schema", value_parser = verify_schema_format)]
+    pub format: SchemaFormat,
+    #[arg(long)]
+    pub table: Option<String>,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvMaskOpts {
+    #[arg(short, long, default_value = "-", value_parser = verity_input_file)]
+    pub input: String,
+    #[command(flatten)]
+    pub read: CsvReadOpts,
+    #[arg(short, long, value_parser = verity_input_file)]
+    pub key: String,
+    #[arg(short, long = "column", required = true, value_parser = parse_mask_rule)]
+    pub columns: Vec<MaskRule>,
+    #[arg(long, default_value_t = 16)]
+    pub length: usize,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    #[arg(short, long, default_value = "csv", value_parser = output_format_parse)]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvEncryptOpts {
+    #[arg(short, long, default_value = "-", value_parser = verity_input_file)]
+    pub input: String,
+    #[command(flatten)]
+    pub read: CsvReadOpts,
+    #[arg(short, long, value_parser = verity_input_file)]
+    pub key: String,
+    #[arg(short, long = "column", required = true, value_delimiter = ',')]
+    pub columns: Vec<String>,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    #[arg(short, long, default_value = "csv", value_parser = output_format_parse)]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Parser)]
+pub struct CsvDecryptOpts {
+    #[arg(short, long, default_value = "-", value_parser = verity_input_file)]
+    pub input: String,
+    #[command(flatten)]
+    pub read: CsvReadOpts,
+    #[arg(short, long, value_parser = verity_input_file)]
+    pub key: String,
+    #[arg(short, long = "column", required = true, value_delimiter = ',')]
+    pub columns: Vec<String>,
+    #[arg(short, long, default_value = "-")]
+    pub output: String,
+    #[arg(short, long, default_value = "csv", value_parser = output_format_parse)]
+    pub format: OutputFormat,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum RowSelection {
+    All,
+    Head(usize),
+    Tail(usize),
+    Range(usize, usize),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct FakeColumn {
+    pub name: String,
+    pub kind: FakeKind,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum FakeKind {
+    Name,
+    Email,
+    Uuid,
+    Seq,
+    Int(i64, i64),
+    Date(NaiveDate, NaiveDate),
+    Enum(Vec<String>),
+    Regex(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SplitBy {
+    Rows(u64),
+    Bytes(u64),
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum JoinKind {
+    Inner,
+    Left,
+    Full,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AggFunc {
+    Count,
+    Sum,
+    Avg,
+    Min,
+    Max,
+    Distinct,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Aggregation {
+    pub func: AggFunc,
+    pub column: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct MaskRule {
+    pub column: String,
+    pub mode: MaskMode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MaskMode {
+    Hash,
+    Email,
+    Last(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SqlDialect {
+    Sqlite,
+    Postgres,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ParquetCompression {
+    None,
+    Snappy,
+    Gzip,
+    Zstd,
+    Lz4,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum SchemaFormat {
+    JsonSchema,
+    Sql(SqlDialect),
+}
+
+impl Default for CsvWriteOpts {
+    fn default() -> Self {
+        CsvWriteOpts {
+            toml_root: None,
+            table: None,
+            dialect: SqlDialect::Sqlite,
+            batch_size: 500,
+            compression: ParquetCompression::Snappy,
+        }
+    }
 }
 
 impl CmdExector for CsvOpts {
     async fn execute(self) -> anyhow::Result<()> {
+        if let Some(cmd) = self.cmd {
+            return cmd.execute().await;
+        }
+        if self.reverse {
+            let output = self.output.unwrap_or_else(|| "output.csv".to_string());
+            return process_csv_reverse(
+                &self.input,
+                &output,
+                self.format,
+                &self.separator,
+                self.write.toml_root.as_deref(),
+            );
+        }
         let output = if let Some(output) = self.output {
             output.clone()
         } else {
             format!("output.{}", self.format)
         };
-        process_csv(&self.input, output, self.format)
+        process_csv(
+            &self.input,
+            &output,
+            self.format,
+            &self.read,
+            &self.infer,
+            &self.write,
+        )
+    }
+}
+
+impl CmdExector for CsvQueryOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        process_csv_query(
+            &self.input,
+            &self.read,
+            &self.query,
+            &self.output,
+            self.format,
+        )
+    }
+}
+
+impl CmdExector for CsvStatsOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        process_csv_stats(&self.input, &self.read, self.top, &self.output, self.format)
+    }
+}
+
+impl CmdExector for CsvDiffOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        process_csv_diff(
+            &self.old,
+            &self.new,
+            &self.key,
+            &self.read,
+            &self.output,
+            self.format,
+        )
+    }
+}
+
+impl CmdExector for CsvJoinOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        process_csv_join(
+            &self.left,
+            &self.right,
+            &self.key,
+            self.how,
+            &self.read,
+            &self.output,
+            self.format,
+        )
+    }
+}
+
+impl CmdExector for CsvConcatOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        process_csv_concat(&self.inputs, &self.read, &self.output, self.format)
+    }
+}
+
+impl CmdExector for CsvViewOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let selection = match (self.head, self.tail, self.range) {
+            (Some(n), _, _) => RowSelection::Head(n),
+            (_, Some(n), _) => RowSelection::Tail(n),
+            (_, _, Some((start, end))) => RowSelection::Range(start, end),
+            _ => RowSelection::All,
+        };
+        process_csv_view(
+            &self.input,
+            &self.read,
+            selection,
+            self.page_size,
+            self.width,
+            self.max_cell_width,
+        )
+    }
+}
+
+impl CmdExector for CsvValidateOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        process_csv_validate(
+            &self.input,
+            &self.read,
+            &self.schema,
+            &self.output,
+            self.format,
+        )
+    }
+}
+
+impl CmdExector for CsvLintOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        process_csv_lint(
+            &self.input,
+            &self.read,
+            &self.output,
+            self.format,
+            self.fix.as_deref(),
+            self.fix_delimiter,
+        )
+    }
+}
+
+impl CmdExector for CsvGroupByOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        process_csv_groupby(
+            &self.input,
+            &self.read,
+            &self.key,
+            &self.aggs,
+            &self.output,
+            self.format,
+        )
+    }
+}
+
+impl CmdExector for CsvPivotOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        process_csv_pivot(
+            &self.input,
+            &self.read,
+            &self.key,
+            &self.column,
+            self.agg,
+            &self.output,
+            self.format,
+        )
+    }
+}
+
+impl CmdExector for CsvSampleOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        process_csv_sample(
+            &self.input,
+            &self.read,
+            self.size,
+            self.seed,
+            &self.output,
+            self.format,
+        )
+    }
+}
+
+impl CmdExector for CsvSplitOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let by = match (self.rows, self.bytes) {
+            (Some(rows), _) => SplitBy::Rows(rows),
+            (_, Some(bytes)) => SplitBy::Bytes(bytes),
+            _ => unreachable!("clap requires --rows or --bytes"),
+        };
+        process_csv_split(
+            &self.input,
+            &self.read,
+            by,
+            &self.dir,
+            self.prefix.as_deref(),
+        )
+    }
+}
+
+impl CmdExector for CsvIndexOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        process_csv_index(&self.input, &self.read, self.every)
+    }
+}
+
+impl CmdExector for CsvFakeOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        process_csv_fake(
+            &self.columns,
+            self.rows,
+            self.seed,
+            &self.output,
+            self.format,
+            &self.write,
+        )
+    }
+}
+
+impl CmdExector for CsvSchemaOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        process_csv_schema(
+            &self.input,
+            &self.read,
+            self.format,
+            self.table.as_deref(),
+            &self.output,
+        )
+    }
+}
+
+impl CmdExector for CsvMaskOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        process_csv_mask(
+            &self.input,
+            &self.read,
+            &self.key,
+            &self.columns,
+            self.length,
+            &self.output,
+            self.format,
+        )
+    }
+}
+
+impl CmdExector for CsvEncryptOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        process_csv_encrypt(
+            &self.input,
+            &self.read,
+            &self.key,
+            &self.columns,
+            &self.output,
+            self.format,
+        )
+    }
+}
+
+impl CmdExector for CsvDecryptOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        process_csv_decrypt(
+            &self.input,
+            &self.read,
+            &self.key,
+            &self.columns,
+            &self.output,
+            self.format,
+        )
+    }
+}
+
+// 行号从 1 开始，例如 10..20 表示第 10 到第 20 行
+fn parse_row_range(range: &str) -> anyhow::Result<(usize, usize), &'static str> {
+    let (start, end) = range
+        .split_once("..")
+        .ok_or("Range must look like <start>..<end>")?;
+    let start = start.parse().map_err(|_| "Range start must be a number")?;
+    let end = end.parse().map_err(|_| "Range end must be a number")?;
+    if start == 0 || start > end {
+        return Err("Range must satisfy 1 <= start <= end");
+    }
+    Ok((start, end))
+}
+
+fn verify_join_kind(kind: &str) -> anyhow::Result<JoinKind, anyhow::Error> {
+    kind.parse()
+}
+
+impl FromStr for JoinKind {
+    type Err = anyhow::Error;
+    fn from_str(kind: &str) -> anyhow::Result<Self, Self::Err> {
+        match kind.to_lowercase().as_str() {
+            "inner" => Ok(JoinKind::Inner),
+            "left" => Ok(JoinKind::Left),
+            "full" | "outer" => Ok(JoinKind::Full),
+            v => anyhow::bail!("Unsupported join kind: {}", v),
+        }
+    }
+}
+
+impl fmt::Display for JoinKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl From<JoinKind> for &'static str {
+    fn from(kind: JoinKind) -> Self {
+        match kind {
+            JoinKind::Inner => "inner",
+            JoinKind::Left => "left",
+            JoinKind::Full => "full",
+        }
+    }
+}
+
+fn parse_fake_column(column: &str) -> anyhow::Result<FakeColumn, anyhow::Error> {
+    column.parse()
+}
+
+// <name>:<kind>[:<args>]，如 id:seq、age:int:18..90、born:date:1970-01-01..2005-12-31、
+// tier:enum:free|pro、code:regex:[A-Z]{3}-\d{4}
+impl FromStr for FakeColumn {
+    type Err = anyhow::Error;
+    fn from_str(column: &str) -> anyhow::Result<Self, Self::Err> {
+        let Some((name, kind)) = column.split_once(':') else {
+            anyhow::bail!(
+                "Fake column must be <name>:<kind>[:<args>], got {:?}",
+                column
+            );
+        };
+        if name.is_empty() {
+            anyhow::bail!("Fake column must have a name");
+        }
+        Ok(FakeColumn {
+            name: name.to_string(),
+            kind: kind.parse()?,
+        })
+    }
+}
+
+impl FromStr for FakeKind {
+    type Err = anyhow::Error;
+    fn from_str(kind: &str) -> anyhow::Result<Self, Self::Err> {
+        let (kind, args) = match kind.split_once(':') {
+            Some((kind, args)) => (kind, Some(args)),
+            None => (kind, None),
+        };
+        let range = |default: &str| -> anyhow::Result<(String, String)> {
+            match args.unwrap_or(default).split_once("..") {
+                Some((min, max)) => Ok((min.trim().to_string(), max.trim().to_string())),
+                None => anyhow::bail!("Range must be <min>..<max>, got {:?}", args),
+            }
+        };
+        match (kind.to_lowercase().as_str(), args) {
+            ("name", None) => Ok(FakeKind::Name),
+            ("email", None) => Ok(FakeKind::Email),
+            ("uuid", None) => Ok(FakeKind::Uuid),
+            ("seq", None) => Ok(FakeKind::Seq),
+            ("int", _) => {
+                let (min, max) = range("1..1000")?;
+                let (min, max) = (min.parse()?, max.parse()?);
+                if min > max {
+                    anyhow::bail!("Range start must not be greater than end");
+                }
+                Ok(FakeKind::Int(min, max))
+            }
+            ("date", _) => {
+                let (start, end) = range("2000-01-01..2029-12-31")?;
+                let start = NaiveDate::parse_from_str(&start, "%Y-%m-%d")?;
+                let end = NaiveDate::parse_from_str(&end, "%Y-%m-%d")?;
+                if start > end {
+                    anyhow::bail!("Range start must not be greater than end");
+                }
+                Ok(FakeKind::Date(start, end))
+            }
+            ("enum", Some(values)) if !values.is_empty() => Ok(FakeKind::Enum(
+                values.split('|').map(String::from).collect(),
+            )),
+            ("regex", Some(pattern)) => Ok(FakeKind::Regex(pattern.to_string())),
+            (kind, _) => anyhow::bail!("Unsupported fake column kind: {}", kind),
+        }
+    }
+}
+
+// 字节数，可带 K/M/G 后缀（1024 进制）
+fn parse_size(size: &str) -> anyhow::Result<u64, anyhow::Error> {
+    let size = size.trim();
+    let (number, unit) = match size.char_indices().find(|(_, c)| c.is_ascii_alphabetic()) {
+        Some((i, _)) => size.split_at(i),
+        None => (size, ""),
+    };
+    let multiplier: u64 = match unit.to_uppercase().trim_end_matches('B') {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        _ => anyhow::bail!("Unsupported size unit: {}", unit),
+    };
+    let number = number.trim().parse::<u64>()?;
+    match number.checked_mul(multiplier) {
+        Some(0) | None => anyhow::bail!("Size must be between 1 byte and 2^64 bytes"),
+        Some(bytes) => Ok(bytes),
+    }
+}
+
+fn parse_aggregation(agg: &str) -> anyhow::Result<Aggregation, anyhow::Error> {
+    agg.parse()
+}
+
+// <func>[:<column>]，只有 count 可以省略列名
+impl FromStr for Aggregation {
+    type Err = anyhow::Error;
+    fn from_str(agg: &str) -> anyhow::Result<Self, Self::Err> {
+        let (func, column) = match agg.split_once(':') {
+            Some((func, column)) => (func.parse()?, Some(column.to_string())),
+            None => (agg.parse()?, None),
+        };
+        if column.is_none() && func != AggFunc::Count {
+            anyhow::bail!(
+                "Aggregation {} requires a column, e.g. {}:<column>",
+                func,
+                func
+            );
+        }
+        Ok(Aggregation { func, column })
+    }
+}
+
+// 输出列名：count 或 <func>_<column>
+impl fmt::Display for Aggregation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.column {
+            Some(column) => write!(f, "{}_{}", self.func, column),
+            None => write!(f, "{}", self.func),
+        }
+    }
+}
+
+impl FromStr for AggFunc {
+    type Err = anyhow::Error;
+    fn from_str(func: &str) -> anyhow::Result<Self, Self::Err> {
+        match func.to_lowercase().as_str() {
+            "count" => Ok(AggFunc::Count),
+            "sum" => Ok(AggFunc::Sum),
+            "avg" | "mean" => Ok(AggFunc::Avg),
+            "min" => Ok(AggFunc::Min),
+            "max" => Ok(AggFunc::Max),
+            "distinct" => Ok(AggFunc::Distinct),
+            v => anyhow::bail!("Unsupported aggregation: {}", v),
+        }
+    }
+}
+
+impl fmt::Display for AggFunc {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl From<AggFunc> for &'static str {
+    fn from(func: AggFunc) -> Self {
+        match func {
+            AggFunc::Count => "count",
+            AggFunc::Sum => "sum",
+            AggFunc::Avg => "avg",
+            AggFunc::Min => "min",
+            AggFunc::Max => "max",
+            AggFunc::Distinct => "distinct",
+        }
+    }
+}
+
+// <column>[:<mode>]，mode 为 hash（默认）、email 或 last4 这样的 last<N>
+fn parse_mask_rule(rule: &str) -> anyhow::Result<MaskRule, anyhow::Error> {
+    let (column, mode) = match rule.rsplit_once(':') {
+        Some((column, mode)) => (column, mode.parse()?),
+        None => (rule, MaskMode::Hash),
+    };
+    if column.is_empty() {
+        anyhow::bail!("Mask rule must name a column");
+    }
+    Ok(MaskRule {
+        column: column.to_string(),
+        mode,
+    })
+}
+
+impl FromStr for MaskMode {
+    type Err = anyhow::Error;
+    fn from_str(mode: &str) -> anyhow::Result<Self, Self::Err> {
+        match mode.to_lowercase().as_str() {
+            "hash" => Ok(MaskMode::Hash),
+            "email" => Ok(MaskMode::Email),
+            v => match v.strip_prefix("last").map(str::parse) {
+                Some(Ok(n)) => Ok(MaskMode::Last(n)),
+                _ => anyhow::bail!("Unsupported mask mode: {}", v),
+            },
+        }
+    }
+}
+
+impl fmt::Display for MaskMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MaskMode::Hash => write!(f, "hash"),
+            MaskMode::Email => write!(f, "email"),
+            MaskMode::Last(n) => write!(f, "last{}", n),
+        }
+    }
+}
+
+fn verify_sql_dialect(dialect: &str) -> anyhow::Result<SqlDialect, anyhow::Error> {
+    dialect.parse()
+}
+
+fn verify_parquet_compression(
+    compression: &str,
+) -> anyhow::Result<ParquetCompression, anyhow::Error> {
+    compression.parse()
+}
+
+impl FromStr for ParquetCompression {
+    type Err = anyhow::Error;
+    fn from_str(compression: &str) -> anyhow::Result<Self, Self::Err> {
+        match compression.to_lowercase().as_str() {
+            "none" | "uncompressed" => Ok(ParquetCompression::None),
+            "snappy" => Ok(ParquetCompression::Snappy),
+            "gzip" => Ok(ParquetCompression::Gzip),
+            "zstd" => Ok(ParquetCompression::Zstd),
+            "lz4" => Ok(ParquetCompression::Lz4),
+            v => anyhow::bail!("Unsupported parquet compression: {}", v),
+        }
+    }
+}
+
+impl fmt::Display for ParquetCompression {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl From<ParquetCompression> for &'static str {
+    fn from(compression: ParquetCompression) -> Self {
+        match compression {
+            ParquetCompression::None => "none",
+            ParquetCompression::Snappy => "snappy",
+            ParquetCompression::Gzip => "gzip",
+            ParquetCompression::Zstd => "zstd",
+            ParquetCompression::Lz4 => "lz4",
+        }
+    }
+}
+
+fn verify_schema_format(format: &str) -> anyhow::Result<SchemaFormat, anyhow::Error> {
+    format.parse()
+}
+
+impl FromStr for SqlDialect {
+    type Err = anyhow::Error;
+    fn from_str(dialect: &str) -> anyhow::Result<Self, Self::Err> {
+        match dialect.to_lowercase().as_str() {
+            "sqlite" => Ok(SqlDialect::Sqlite),
+            "postgres" | "postgresql" | "pg" => Ok(SqlDialect::Postgres),
+            v => anyhow::bail!("Unsupported SQL dialect: {}", v),
+        }
+    }
+}
+
+impl fmt::Display for SqlDialect {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl From<SqlDialect> for &'static str {
+    fn from(dialect: SqlDialect) -> Self {
+        match dialect {
+            SqlDialect::Sqlite => "sqlite",
+            SqlDialect::Postgres => "postgres",
+        }
+    }
+}
+
+// json-schema，或者 sqlite/postgres 方言的 CREATE TABLE
+impl FromStr for SchemaFormat {
+    type Err = anyhow::Error;
+    fn from_str(format: &str) -> anyhow::Result<Self, Self::Err> {
+        match format.to_lowercase().as_str() {
+            "json-schema" | "jsonschema" => Ok(SchemaFormat::JsonSchema),
+            v => v
+                .parse()
+                .map(SchemaFormat::Sql)
+                .map_err(|_| anyhow::anyhow!("Unsupported schema format: {}", v)),
+        }
+    }
+}
+
+impl fmt::Display for SchemaFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl From<SchemaFormat> for &'static str {
+    fn from(format: SchemaFormat) -> Self {
+        match format {
+            SchemaFormat::JsonSchema => "json-schema",
+            SchemaFormat::Sql(dialect) => dialect.into(),
+        }
+    }
+}
+
+// 支持 WHATWG 编码标签，如 utf-8、utf-16le、gbk、latin1；不指定时自动检测
+fn parse_encoding(label: &str) -> anyhow::Result<&'static Encoding, &'static str> {
+    Encoding::for_label(label.as_bytes()).ok_or("Unknown encoding label")
+}
+
+fn parse_delimiter(delimiter: &str) -> anyhow::Result<u8, &'static str> {
+    match delimiter {
+        "\\t" | "tab" => Ok(b'\t'),
+        v if v.len() == 1 && v.is_ascii() => Ok(v.as_bytes()[0]),
+        _ => Err("Delimiter must be a single ASCII character"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_delimiter() {
+        assert_eq!(parse_delimiter(";"), Ok(b';'));
+        assert_eq!(parse_delimiter("tab"), Ok(b'\t'));
+        assert_eq!(parse_delimiter("\t"), Ok(b'\t'));
+        assert!(parse_delimiter("ab").is_err());
+        assert!(parse_delimiter("é").is_err());
+    }
+
+    #[test]
+    fn test_csv_read_opts() {
+        let opts = CsvOpts::try_parse_from(["csv", "--encoding", "gbk", "-d", "tab"]).unwrap();
+        assert_eq!(opts.read.encoding, Some(encoding_rs::GBK));
+        assert_eq!(opts.read.delimiter, b'\t');
+        assert!(opts.read.header);
+        assert!(CsvOpts::try_parse_from(["csv", "--encoding", "nope"]).is_err());
+        let opts = CsvOpts::try_parse_from(["csv", "--header", "false"]).unwrap();
+        assert!(!opts.read.header);
+    }
+
+    #[test]
+    fn test_schema_format() {
+        assert_eq!(
+            "json-schema".parse::<SchemaFormat>().unwrap(),
+            SchemaFormat::JsonSchema
+        );
+        assert_eq!(
+            "PG".parse::<SchemaFormat>().unwrap(),
+            SchemaFormat::Sql(SqlDialect::Postgres)
+        );
+        assert_eq!(SchemaFormat::Sql(SqlDialect::Sqlite).to_string(), "sqlite");
+        assert!("mysql".parse::<SchemaFormat>().is_err());
+    }
+
+    #[test]
+    fn test_parse_mask_rule() {
+        let rule = parse_mask_rule("email:email").unwrap();
+        assert_eq!(rule.mode, MaskMode::Email);
+        assert_eq!(parse_mask_rule("name").unwrap().mode, MaskMode::Hash);
+        let rule = parse_mask_rule("a:b:last4").unwrap();
+        assert_eq!(
+            (rule.column.as_str(), rule.mode),
+            ("a:b", MaskMode::Last(4))
+        );
+        assert_eq!(MaskMode::Last(4).to_string(), "last4");
+        assert!(parse_mask_rule("phone:lastx").is_err());
+        assert!(parse_mask_rule(":hash").is_err());
+    }
+
+    #[test]
+    fn test_parse_row_range() {
+        assert_eq!(parse_row_range("10..20"), Ok((10, 20)));
+        assert!(parse_row_range("0..2").is_err());
+        assert!(parse_row_range("5..2").is_err());
+        assert!(parse_row_range("5").is_err());
+    }
+
+    #[test]
+    fn test_parse_fake_column() {
+        let column = parse_fake_column("age:int:18..90").unwrap();
+        assert_eq!(column.name, "age");
+        assert_eq!(column.kind, FakeKind::Int(18, 90));
+        assert_eq!(
+            parse_fake_column("code:regex:[a-z]:\\d").unwrap().kind,
+            FakeKind::Regex("[a-z]:\\d".to_string())
+        );
+        assert_eq!(
+            parse_fake_column("tier:enum:free|pro").unwrap().kind,
+            FakeKind::Enum(vec!["free".to_string(), "pro".to_string()])
+        );
+        assert!(parse_fake_column("age").is_err());
+        assert!(parse_fake_column("age:int:9..1").is_err());
+        assert!(parse_fake_column("born:date:2020-13-01..2021-01-01").is_err());
+        assert!(parse_fake_column("x:phone").is_err());
+    }
+
+    #[test]
+    fn test_parse_size() {
+        assert_eq!(parse_size("512").unwrap(), 512);
+        assert_eq!(parse_size("10K").unwrap(), 10 * 1024);
+        assert_eq!(parse_size("2mb").unwrap(), 2 * 1024 * 1024);
+        assert!(parse_size("0").is_err());
+        assert!(parse_size("1T").is_err());
+    }
+
+    #[test]
+    fn test_parse_aggregation() {
+        let agg = parse_aggregation("Mean:score").unwrap();
+        assert_eq!(agg.func, AggFunc::Avg);
+        assert_eq!(agg.to_string(), "avg_score");
+        assert_eq!(parse_aggregation("count").unwrap().to_string(), "count");
+        assert!(parse_aggregation("sum").is_err());
+        assert!(parse_aggregation("median:score").is_err());
     }
 }
@@ -1,35 +1,180 @@
-use crate::{process_gen_pass, CmdExector};
-use clap::Parser;
+use super::verity_input_file;
+use crate::{
+    load_password_policy, load_wordlist, process_gen_pass, process_gen_passphrase,
+    process_pass_strength, CmdExector, PasswordPolicy, EFF_WORDLIST,
+};
+use clap::{ArgAction, Parser};
+use enum_dispatch::enum_dispatch;
+use std::io::Read;
 
 #[derive(Debug, Parser)]
+#[command(args_conflicts_with_subcommands = true)]
 pub struct GenPassOpts {
-    #[arg(short, long, default_value = "16")]
-    pub length: usize,
+    #[command(subcommand)]
+    pub cmd: Option<GenPassSubCommand>,
+    #[arg(short, long)]
+    pub length: Option<usize>,
     #[arg(short, long, default_value = "1")]
     pub count: usize,
-    #[arg(long, default_value_t = true)]
-    pub uppercase: bool,
-    #[arg(long, default_value_t = true)]
-    pub lowercase: bool,
-    #[arg(long, default_value_t = true)]
-    pub number: bool,
-    #[arg(long, default_value_t = true)]
-    pub symbol: bool,
+    #[arg(long, action = ArgAction::Set, num_args = 0..=1, default_missing_value = "true")]
+    pub uppercase: Option<bool>,
+    #[arg(long, action = ArgAction::Set, num_args = 0..=1, default_missing_value = "true")]
+    pub lowercase: Option<bool>,
+    #[arg(long, action = ArgAction::Set, num_args = 0..=1, default_missing_value = "true")]
+    pub number: Option<bool>,
+    #[arg(long, action = ArgAction::Set, num_args = 0..=1, default_missing_value = "true")]
+    pub symbol: Option<bool>,
+    #[arg(long)]
+    pub min_uppercase: Option<usize>,
+    #[arg(long)]
+    pub min_lowercase: Option<usize>,
+    #[arg(long)]
+    pub min_number: Option<usize>,
+    #[arg(long)]
+    pub min_symbol: Option<usize>,
+    #[arg(long)]
+    pub symbols: Option<String>,
+    #[arg(long)]
+    pub exclude_ambiguous: bool,
+    #[arg(long)]
+    pub no_repeat: bool,
+    #[arg(long, value_parser = verity_input_file)]
+    pub profile: Option<String>,
+    #[arg(long)]
+    pub words: Option<usize>,
+    #[arg(long, value_parser = verity_input_file)]
+    pub wordlist: Option<String>,
+    #[arg(long, default_value = "-")]
+    pub separator: String,
+    #[arg(long)]
+    pub capitalize: bool,
+    #[arg(long, default_value_t = 0)]
+    pub digits: usize,
+}
+
+#[derive(Debug, Parser)]
+#[enum_dispatch(CmdExector)]
+pub enum GenPassSubCommand {
+    #[command(about = "Estimate the strength of a password read from stdin or a file")]
+    Check(GenPassCheckOpts),
+}
+
+#[derive(Debug, Parser)]
+pub struct GenPassCheckOpts {
+    #[arg(short, long, value_parser = verity_input_file, default_value = "-")]
+    pub input: String,
+    // 给出 profile 时额外检查密码是否符合该策略
+    #[arg(long, value_parser = verity_input_file)]
+    pub profile: Option<String>,
+}
+
+impl GenPassOpts {
+    // 以 profile 文件（或默认策略）为基础，命令行中显式给出的参数优先
+    fn policy(&self) -> anyhow::Result<PasswordPolicy> {
+        let mut policy = match &self.profile {
+            Some(profile) => load_password_policy(profile)?,
+            None => PasswordPolicy::default(),
+        };
+        policy.length = self.length.unwrap_or(policy.length);
+        policy.uppercase = self.uppercase.unwrap_or(policy.uppercase);
+        policy.lowercase = self.lowercase.unwrap_or(policy.lowercase);
+        policy.number = self.number.unwrap_or(policy.number);
+        policy.symbol = self.symbol.unwrap_or(policy.symbol);
+        policy.min_uppercase = self.min_uppercase.unwrap_or(policy.min_uppercase);
+        policy.min_lowercase = self.min_lowercase.unwrap_or(policy.min_lowercase);
+        policy.min_number = self.min_number.unwrap_or(policy.min_number);
+        policy.min_symbol = self.min_symbol.unwrap_or(policy.min_symbol);
+        if let Some(symbols) = &self.symbols {
+            policy.symbols = symbols.clone();
+        }
+        policy.exclude_ambiguous |= self.exclude_ambiguous;
+        policy.no_repeat |= self.no_repeat;
+        Ok(policy)
+    }
 }
 
 impl CmdExector for GenPassOpts {
     async fn execute(self) -> anyhow::Result<()> {
-        let passwords = process_gen_pass(
-            self.length,
-            self.count,
-            self.uppercase,
-            self.lowercase,
-            self.number,
-            self.symbol,
-        )?;
+        if let Some(cmd) = self.cmd {
+            return cmd.execute().await;
+        }
+        if let Some(words) = self.words {
+            // 默认使用内置的 EFF 词表，--wordlist 可替换为自定义词表
+            let wordlist = match &self.wordlist {
+                Some(path) => load_wordlist(&std::fs::read_to_string(path)?),
+                None => load_wordlist(EFF_WORDLIST),
+            };
+            let (phrases, entropy) = process_gen_passphrase(
+                &wordlist,
+                words,
+                self.count,
+                &self.separator,
+                self.capitalize,
+                self.digits,
+            )?;
+            for phrase in phrases {
+                println!("{}", phrase);
+            }
+            // 熵输出到 stderr，不影响管道中使用生成的口令
+            eprintln!("Entropy: {:.1} bits", entropy);
+            return Ok(());
+        }
+        let policy = self.policy()?;
+        let passwords = process_gen_pass(&policy, self.count)?;
         for password in passwords {
             println!("{}", password);
         }
+        eprintln!("Entropy: {:.1} bits", policy.entropy()?);
         Ok(())
     }
 }
+
+impl CmdExector for GenPassCheckOpts {
+    async fn execute(self) -> anyhow::Result<()> {
+        let mut content = String::new();
+        if self.input == "-" {
+            std::io::stdin().read_to_string(&mut content)?;
+        } else {
+            std::fs::File::open(&self.input)?.read_to_string(&mut content)?;
+        }
+        // 只取第一行，去掉结尾的换行
+        let password = content.lines().next().unwrap_or_default();
+        let policy = match &self.profile {
+            Some(profile) => load_password_policy(profile)?,
+            None => PasswordPolicy::default(),
+        };
+        let report = process_pass_strength(password, &policy);
+        print!("{}", report.render(self.profile.is_some()));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_policy_overrides() {
+        let opts = GenPassOpts::try_parse_from([
+            "genpass",
+            "-l",
+            "20",
+            "--symbol",
+            "false",
+            "--uppercase",
+            "--min-number",
+            "3",
+            "--no-repeat",
+        ])
+        .unwrap();
+        let policy = opts.policy().unwrap();
+        assert_eq!(policy.length, 20);
+        assert!(!policy.symbol && policy.uppercase && policy.no_repeat);
+        assert_eq!(policy.min_number, 3);
+        assert_eq!(policy.min_lowercase, 1);
+
+        let opts = GenPassOpts::try_parse_from(["genpass", "check"]).unwrap();
+        assert!(matches!(opts.cmd, Some(GenPassSubCommand::Check(_))));
+        assert!(GenPassOpts::try_parse_from(["genpass", "-l", "8", "check"]).is_err());
+    }
+}
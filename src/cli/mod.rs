@@ -40,8 +40,15 @@ pub enum SubCommand {
 #[derive(Debug, Clone, Copy)]
 pub enum OutputFormat {
     Json,
+    Ndjson,
     Yaml,
     Toml,
+    Csv,
+    Sql,
+    Sqlite,
+    Xlsx,
+    Parquet,
+    Arrow,
 }
 
 fn verity_input_file(filename: &str) -> anyhow::Result<String, &'static str> {
@@ -99,8 +106,15 @@ impl From<OutputFormat> for &'static str {
     fn from(format: OutputFormat) -> Self {
         match format {
             OutputFormat::Json => "json",
+            OutputFormat::Ndjson => "ndjson",
             OutputFormat::Yaml => "yaml",
             OutputFormat::Toml => "toml",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Sql => "sql",
+            OutputFormat::Sqlite => "sqlite",
+            OutputFormat::Xlsx => "xlsx",
+            OutputFormat::Parquet => "parquet",
+            OutputFormat::Arrow => "arrow",
         }
     }
 }
@@ -110,8 +124,15 @@ impl FromStr for OutputFormat {
     fn from_str(format: &str) -> anyhow::Result<Self, Self::Err> {
         match format.to_lowercase().as_str() {
             "json" => Ok(OutputFormat::Json),
+            "ndjson" | "jsonl" => Ok(OutputFormat::Ndjson),
             "yaml" => Ok(OutputFormat::Yaml),
             "toml" => Ok(OutputFormat::Toml),
+            "csv" => Ok(OutputFormat::Csv),
+            "sql" => Ok(OutputFormat::Sql),
+            "sqlite" | "sqlite3" | "db" => Ok(OutputFormat::Sqlite),
+            "xlsx" => Ok(OutputFormat::Xlsx),
+            "parquet" => Ok(OutputFormat::Parquet),
+            "arrow" | "ipc" | "feather" => Ok(OutputFormat::Arrow),
             v => anyhow::bail!("Unsupported output format: {}", v),
         }
     }
@@ -158,7 +158,9 @@ impl FromStr for CryptFormat {
     fn from_str(format: &str) -> anyhow::Result<Self, Self::Err> {
         match format.to_lowercase().as_str() {
             "chacha20poly1305" => Ok(CryptFormat::ChaCha20Poly1305),
-            v => unreachable!("Unsupported format: {:?}", v),
+            "blake" | "blake3" => Ok(CryptFormat::Blake),
+            "ed25519" => Ok(CryptFormat::Ed25519),
+            v => anyhow::bail!("Unsupported format: {:?}", v),
         }
     }
 }
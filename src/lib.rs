@@ -4,10 +4,31 @@ mod process;
 pub use cli::*;
 use enum_dispatch::enum_dispatch;
 pub use process::base64_ed::{process_base64_decode, process_base64_encode};
+pub use process::csv_concat::process_csv_concat;
 pub use process::csv_convert::process_csv;
-pub use process::gen_pass::process_gen_pass;
+pub use process::csv_crypt::{process_csv_decrypt, process_csv_encrypt};
+pub use process::csv_diff::process_csv_diff;
+pub use process::csv_fake::process_csv_fake;
+pub use process::csv_groupby::{process_csv_groupby, process_csv_pivot};
+pub use process::csv_index::process_csv_index;
+pub use process::csv_join::process_csv_join;
+pub use process::csv_lint::process_csv_lint;
+pub use process::csv_mask::process_csv_mask;
+pub use process::csv_query::process_csv_query;
+pub use process::csv_reverse::process_csv_reverse;
+pub use process::csv_sample::process_csv_sample;
+pub use process::csv_schema::process_csv_schema;
+pub use process::csv_split::process_csv_split;
+pub use process::csv_stats::process_csv_stats;
+pub use process::csv_validate::process_csv_validate;
+pub use process::csv_view::process_csv_view;
+pub use process::gen_pass::{
+    load_password_policy, load_wordlist, process_gen_pass, process_gen_passphrase, CharClass,
+    PasswordPolicy, EFF_WORDLIST,
+};
 pub use process::http_serve::process_http_serve;
 pub use process::jwt::{process_jwt_sign, process_jwt_verify};
+pub use process::pass_strength::{process_pass_strength, StrengthReport};
 pub use process::text_op::{
     process_text_decrypt, process_text_encrypt, process_text_generate, process_text_sign,
     process_text_verify,
@@ -0,0 +1,82 @@
+use super::csv_reader::open_csv;
+use super::data_to_output;
+use super::record::create_record_writer;
+use crate::cli::{CsvReadOpts, OutputFormat};
+use anyhow::Result;
+use csv::{Reader, StringRecord};
+use serde_json::{Map, Value};
+use std::io::{Read, Write};
+
+pub fn process_csv_concat(
+    inputs: &[String],
+    read: &CsvReadOpts,
+    output: &str,
+    format: OutputFormat,
+) -> Result<()> {
+    let sources = inputs
+        .iter()
+        .map(|input| open_csv(input, read))
+        .collect::<Result<Vec<_>>>()?;
+    let writer = data_to_output(output)?;
+    concat_csv(sources, writer, format)
+}
+
+fn concat_csv<'a>(
+    sources: Vec<(Reader<impl Read>, StringRecord)>,
+    writer: Box<dyn Write + 'a>,
+    format: OutputFormat,
+) -> Result<()> {
+    // 所有文件表头的并集，按首次出现的顺序排列，缺失的列填空值
+    let mut headers: Vec<String> = Vec::new();
+    for (_, source_headers) in &sources {
+        for name in source_headers.iter() {
+            if !headers.iter().any(|h| h == name) {
+                headers.push(name.to_string());
+            }
+        }
+    }
+
+    let mut writer = create_record_writer(format, writer)?;
+    for (mut reader, source_headers) in sources {
+        let index = headers
+            .iter()
+            .map(|name| source_headers.iter().position(|h| h == name))
+            .collect::<Vec<_>>();
+        let mut record = StringRecord::new();
+        while reader.read_record(&mut record)? {
+            let map = headers
+                .iter()
+                .zip(&index)
+                .map(|(name, i)| {
+                    let value = i.and_then(|i| record.get(i)).unwrap_or_default();
+                    (name.clone(), Value::String(value.to_string()))
+                })
+                .collect::<Map<_, _>>();
+            writer.write_record(&Value::Object(map))?;
+        }
+    }
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concat_csv() {
+        let sources = ["id,name\n1,alice\n", "name,team\nbob,b\n"]
+            .iter()
+            .map(|data| {
+                let mut reader = Reader::from_reader(data.as_bytes());
+                let headers = reader.headers().unwrap().clone();
+                (reader, headers)
+            })
+            .collect::<Vec<_>>();
+        let mut buf = Vec::new();
+        concat_csv(sources, Box::new(&mut buf), OutputFormat::Csv).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "id,name,team\n1,alice,\n,bob,b\n"
+        );
+    }
+}
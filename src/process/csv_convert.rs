@@ -1,25 +1,113 @@
-use crate::cli::OutputFormat;
-use anyhow::Result;
-use csv::Reader;
-use serde_json::Value;
-use std::fs;
-
-pub fn process_csv(input: &str, output: String, format: OutputFormat) -> Result<()> {
-    let mut reader = Reader::from_path(input)?;
-    let headers = reader.headers()?.clone();
-    let mut records = Vec::new();
-    for result in reader.records() {
-        let record = result?;
-        // zip 用于迭代器之间的元素配对，collect 用于将配对的元素转换为 json 对象
-        let json_value = headers.iter().zip(record.iter()).collect::<Value>();
-        records.push(json_value);
-    }
-    println!("{:?}", records);
-    let content = match format {
-        OutputFormat::Json => serde_json::to_string(&records)?,
-        OutputFormat::Yaml => serde_yaml::to_string(&records)?,
-        OutputFormat::Toml => toml::to_string(&records)?,
+use super::csv_reader::open_csv;
+use super::record::infer::sample_column_types;
+use super::record::{open_record_writer, RecordWrite};
+use super::sql::default_table_name;
+use crate::cli::{CsvInferOpts, CsvReadOpts, CsvWriteOpts, OutputFormat};
+use anyhow::{Context, Result};
+use csv::{Reader, StringRecord};
+use std::io::Read;
+
+pub fn process_csv(
+    input: &str,
+    output: &str,
+    format: OutputFormat,
+    read: &CsvReadOpts,
+    infer: &CsvInferOpts,
+    write: &CsvWriteOpts,
+) -> Result<()> {
+    let (reader, headers) = open_csv(input, read)?;
+    let write = CsvWriteOpts {
+        table: Some(
+            write
+                .table
+                .clone()
+                .unwrap_or_else(|| default_table_name(input)),
+        ),
+        ..write.clone()
+    };
+    // 列式格式需要确定的列类型，总是开启推断
+    let infer = CsvInferOpts {
+        infer: infer.infer || matches!(format, OutputFormat::Parquet | OutputFormat::Arrow),
+        ..infer.clone()
     };
-    fs::write(output, content)?;
-    Ok(())
+    let writer = open_record_writer(format, output, &write)?;
+    convert_csv(reader, &headers, writer, &infer)
+}
+
+// 逐条读取并写出记录，避免将整个文件加载到内存
+fn convert_csv(
+    mut reader: Reader<impl Read>,
+    headers: &StringRecord,
+    mut writer: Box<dyn RecordWrite + '_>,
+    infer: &CsvInferOpts,
+) -> Result<()> {
+    let (types, samples) = sample_column_types(&mut reader, headers, infer)?;
+    writer.set_headers(&headers.iter().map(String::from).collect::<Vec<_>>());
+    for record in &samples {
+        writer.write_record(&types.to_value(headers, record))?;
+    }
+    let mut record = StringRecord::new();
+    // csv 错误只指出第一处问题，提示用 lint 查看全部结构问题
+    while reader
+        .read_record(&mut record)
+        .context("Malformed CSV, run `csv lint` to list every structural problem")?
+    {
+        writer.write_record(&types.to_value(headers, &record))?;
+    }
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::process::record::create_record_writer;
+    use serde_json::Value;
+    use std::fs::File;
+
+    fn convert(format: OutputFormat, infer: bool) -> Vec<u8> {
+        let (reader, headers) = juventus();
+        let mut buf = Vec::new();
+        let writer = create_record_writer(format, Box::new(&mut buf)).unwrap();
+        let infer = CsvInferOpts {
+            infer,
+            sample: 10,
+            types: None,
+        };
+        convert_csv(reader, &headers, writer, &infer).unwrap();
+        buf
+    }
+
+    fn juventus() -> (Reader<File>, StringRecord) {
+        let mut reader = Reader::from_path("assets/juventus.csv").unwrap();
+        let headers = reader.headers().unwrap().clone();
+        (reader, headers)
+    }
+
+    #[test]
+    fn test_convert_csv_ndjson() {
+        let buf = convert(OutputFormat::Ndjson, false);
+        let content = String::from_utf8(buf).unwrap();
+        let lines = content.lines().collect::<Vec<_>>();
+        assert_eq!(lines.len(), 27);
+        let first: Value = serde_json::from_str(lines[0]).unwrap();
+        assert_eq!(first["Name"], "Wojciech Szczesny");
+        assert_eq!(first["Kit Number"], "1");
+    }
+
+    #[test]
+    fn test_convert_csv_json() {
+        let buf = convert(OutputFormat::Json, false);
+        let records: Vec<Value> = serde_json::from_slice(&buf).unwrap();
+        assert_eq!(records.len(), 27);
+    }
+
+    #[test]
+    fn test_convert_csv_infer() {
+        let buf = convert(OutputFormat::Json, true);
+        let records: Vec<Value> = serde_json::from_slice(&buf).unwrap();
+        assert_eq!(records.len(), 27);
+        assert_eq!(records[0]["Kit Number"], 1);
+        assert_eq!(records[26]["Name"], "Mario Mandzukic");
+        assert_eq!(records[26]["Kit Number"], 17);
+    }
 }
@@ -0,0 +1,127 @@
+use super::csv_reader::open_csv;
+use super::data_to_output;
+use super::record::create_record_writer;
+use super::text::chacha20poly1305::ChaCha20Poly1305EnDe;
+use crate::cli::{CsvReadOpts, OutputFormat};
+use anyhow::{anyhow, Result};
+use csv::{Reader, StringRecord};
+use serde_json::{Map, Value};
+use std::io::{Read, Write};
+
+pub fn process_csv_encrypt(
+    input: &str,
+    read: &CsvReadOpts,
+    key: &str,
+    columns: &[String],
+    output: &str,
+    format: OutputFormat,
+) -> Result<()> {
+    let cipher = ChaCha20Poly1305EnDe::try_new(&std::fs::read(key)?)?;
+    let (reader, headers) = open_csv(input, read)?;
+    let writer = data_to_output(output)?;
+    crypt_csv(reader, &headers, columns, writer, format, |cell| {
+        cipher.encrypt_cell(cell.as_bytes())
+    })
+}
+
+pub fn process_csv_decrypt(
+    input: &str,
+    read: &CsvReadOpts,
+    key: &str,
+    columns: &[String],
+    output: &str,
+    format: OutputFormat,
+) -> Result<()> {
+    let cipher = ChaCha20Poly1305EnDe::try_new(&std::fs::read(key)?)?;
+    let (reader, headers) = open_csv(input, read)?;
+    let writer = data_to_output(output)?;
+    crypt_csv(reader, &headers, columns, writer, format, |cell| {
+        let plain_text = cipher.decrypt_cell(cell)?;
+        String::from_utf8(plain_text).map_err(|_| anyhow!("Decrypted cell contains invalid UTF-8"))
+    })
+}
+
+// 只转换指定列，空单元格保持为空，其余列原样输出
+fn crypt_csv<'a>(
+    mut reader: Reader<impl Read>,
+    headers: &StringRecord,
+    columns: &[String],
+    writer: Box<dyn Write + 'a>,
+    format: OutputFormat,
+    convert: impl Fn(&str) -> Result<String>,
+) -> Result<()> {
+    let mut selected = vec![false; headers.len()];
+    for column in columns {
+        let index = headers
+            .iter()
+            .position(|h| h == column)
+            .ok_or_else(|| anyhow!("Column {:?} not found", column))?;
+        selected[index] = true;
+    }
+
+    let mut writer = create_record_writer(format, writer)?;
+    let mut record = StringRecord::new();
+    while reader.read_record(&mut record)? {
+        let line = record.position().map(|p| p.line()).unwrap_or_default();
+        let mut map = Map::new();
+        for (i, name) in headers.iter().enumerate() {
+            let value = record.get(i).unwrap_or_default();
+            let value = if selected[i] && !value.is_empty() {
+                convert(value).map_err(|e| anyhow!("line {}, column {:?}: {}", line, name, e))?
+            } else {
+                value.to_string()
+            };
+            map.insert(name.to_string(), Value::String(value));
+        }
+        writer.write_record(&Value::Object(map))?;
+    }
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(
+        data: &str,
+        columns: &[&str],
+        convert: impl Fn(&str) -> Result<String>,
+    ) -> Result<String> {
+        let mut reader = Reader::from_reader(data.as_bytes());
+        let headers = reader.headers()?.clone();
+        let columns = columns.iter().map(|c| c.to_string()).collect::<Vec<_>>();
+        let mut buf = Vec::new();
+        crypt_csv(
+            reader,
+            &headers,
+            &columns,
+            Box::new(&mut buf),
+            OutputFormat::Csv,
+            convert,
+        )?;
+        Ok(String::from_utf8(buf)?)
+    }
+
+    #[test]
+    fn test_crypt_csv_round_trip() {
+        let key = [[1u8; 12].as_slice(), [2u8; 32].as_slice()].concat();
+        let cipher = ChaCha20Poly1305EnDe::try_new(&key).unwrap();
+        let data = "id,ssn,note\n1,123-45-6789,a\n2,,b\n";
+        let encrypted = run(data, &["ssn"], |c| cipher.encrypt_cell(c.as_bytes())).unwrap();
+        assert!(!encrypted.contains("123-45-6789"));
+        assert!(encrypted.ends_with("\n2,,b\n"));
+
+        let decrypted = run(&encrypted, &["ssn"], |c| {
+            Ok(String::from_utf8(cipher.decrypt_cell(c)?)?)
+        })
+        .unwrap();
+        assert_eq!(decrypted, data);
+
+        let err = run(data, &["note"], |c| {
+            Ok(String::from_utf8(cipher.decrypt_cell(c)?)?)
+        })
+        .unwrap_err();
+        assert!(err.to_string().starts_with("line 2, column \"note\""));
+        assert!(run(data, &["missing"], |c| Ok(c.to_string())).is_err());
+    }
+}
@@ -0,0 +1,283 @@
+use super::csv_reader::open_csv;
+use super::record::create_record_writer;
+use super::{column_indexes, data_to_output, row_key};
+use crate::cli::{CsvReadOpts, OutputFormat};
+use anyhow::{bail, Result};
+use csv::{Reader, StringRecord};
+use serde_json::{json, Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write as _;
+use std::io::Read;
+
+#[derive(Debug, Default)]
+struct CsvDiff {
+    key_columns: Vec<String>,
+    columns_added: Vec<String>,
+    columns_removed: Vec<String>,
+    added: Vec<(Vec<String>, Map<String, Value>)>,
+    removed: Vec<(Vec<String>, Map<String, Value>)>,
+    changed: Vec<(Vec<String>, Vec<CellChange>)>,
+}
+
+#[derive(Debug, PartialEq)]
+struct CellChange {
+    column: String,
+    before: String,
+    after: String,
+}
+
+const PATCH_FIELDS: [&str; 5] = ["op", "path", "column", "old", "value"];
+
+pub fn process_csv_diff(
+    old: &str,
+    new: &str,
+    keys: &[String],
+    read: &CsvReadOpts,
+    output: &str,
+    format: Option<OutputFormat>,
+) -> Result<()> {
+    let (old_reader, old_headers) = open_csv(old, read)?;
+    let (new_reader, new_headers) = open_csv(new, read)?;
+    let diff = diff_csv(old_reader, &old_headers, new_reader, &new_headers, keys)?;
+    let mut writer = data_to_output(output)?;
+    match format {
+        Some(format) => {
+            let mut writer = create_record_writer(format, writer)?;
+            // 各类操作的字段不同，表格格式按全部字段输出
+            writer.set_headers(&PATCH_FIELDS.map(String::from));
+            for op in diff.patch() {
+                writer.write_record(&op)?;
+            }
+            writer.finish()
+        }
+        None => {
+            writer.write_all(diff.summary().as_bytes())?;
+            writer.flush()?;
+            Ok(())
+        }
+    }
+}
+
+fn diff_csv(
+    mut old: Reader<impl Read>,
+    old_headers: &StringRecord,
+    mut new: Reader<impl Read>,
+    new_headers: &StringRecord,
+    keys: &[String],
+) -> Result<CsvDiff> {
+    let old_keys = column_indexes(old_headers, keys)?;
+    let new_keys = column_indexes(new_headers, keys)?;
+
+    // 旧文件按 key 建立索引，新文件流式比对
+    let mut order = Vec::new();
+    let mut old_rows = HashMap::new();
+    for record in old.records() {
+        let record = record?;
+        let key = row_key(&record, &old_keys);
+        if old_rows.insert(key.clone(), record).is_some() {
+            bail!("Duplicate key {:?} in old file", key);
+        }
+        order.push(key);
+    }
+
+    // 只比较两边都存在的列
+    let common = new_headers
+        .iter()
+        .enumerate()
+        .filter_map(|(j, name)| {
+            let i = old_headers.iter().position(|h| h == name)?;
+            Some((name.to_string(), i, j))
+        })
+        .collect::<Vec<_>>();
+    let mut diff = CsvDiff {
+        key_columns: keys.to_vec(),
+        columns_added: new_headers
+            .iter()
+            .filter(|h| !old_headers.iter().any(|o| o == *h))
+            .map(String::from)
+            .collect(),
+        columns_removed: old_headers
+            .iter()
+            .filter(|h| !new_headers.iter().any(|n| n == *h))
+            .map(String::from)
+            .collect(),
+        ..Default::default()
+    };
+
+    let mut seen = HashSet::new();
+    for record in new.records() {
+        let record = record?;
+        let key = row_key(&record, &new_keys);
+        if !seen.insert(key.clone()) {
+            bail!("Duplicate key {:?} in new file", key);
+        }
+        match old_rows.remove(&key) {
+            Some(old_record) => {
+                let changes = common
+                    .iter()
+                    .filter_map(|(column, i, j)| {
+                        let before = old_record.get(*i).unwrap_or_default();
+                        let after = record.get(*j).unwrap_or_default();
+                        (before != after).then(|| CellChange {
+                            column: column.clone(),
+                            before: before.to_string(),
+                            after: after.to_string(),
+                        })
+                    })
+                    .collect::<Vec<_>>();
+                if !changes.is_empty() {
+                    diff.changed.push((key, changes));
+                }
+            }
+            None => diff.added.push((key, row_map(new_headers, &record))),
+        }
+    }
+    for key in order {
+        if let Some(record) = old_rows.remove(&key) {
+            diff.removed.push((key, row_map(old_headers, &record)));
+        }
+    }
+    Ok(diff)
+}
+
+fn row_map(headers: &StringRecord, record: &StringRecord) -> Map<String, Value> {
+    headers
+        .iter()
+        .zip(record.iter())
+        .map(|(h, v)| (h.to_string(), Value::String(v.to_string())))
+        .collect()
+}
+
+impl CsvDiff {
+    fn key_text(&self, key: &[String]) -> String {
+        self.key_columns
+            .iter()
+            .zip(key)
+            .map(|(name, value)| format!("{}={}", name, value))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    // 类似 json patch (RFC 6902) 的操作列表，路径为 /<key>/<column>
+    fn patch(&self) -> Vec<Value> {
+        let mut ops = Vec::new();
+        for column in &self.columns_added {
+            ops.push(json!({"op": "add", "path": "/", "column": column}));
+        }
+        for column in &self.columns_removed {
+            ops.push(json!({"op": "remove", "path": "/", "column": column}));
+        }
+        for (key, row) in &self.removed {
+            ops.push(json!({"op": "remove", "path": pointer(key, None), "old": row}));
+        }
+        for (key, row) in &self.added {
+            ops.push(json!({"op": "add", "path": pointer(key, None), "value": row}));
+        }
+        for (key, changes) in &self.changed {
+            for change in changes {
+                ops.push(json!({
+                    "op": "replace",
+                    "path": pointer(key, Some(&change.column)),
+                    "old": change.before,
+                    "value": change.after,
+                }));
+            }
+        }
+        ops
+    }
+
+    fn summary(&self) -> String {
+        let mut out = String::new();
+        let _ = writeln!(
+            out,
+            "added: {}, removed: {}, changed: {}",
+            self.added.len(),
+            self.removed.len(),
+            self.changed.len()
+        );
+        for column in &self.columns_added {
+            let _ = writeln!(out, "+ column {}", column);
+        }
+        for column in &self.columns_removed {
+            let _ = writeln!(out, "- column {}", column);
+        }
+        for (key, _) in &self.added {
+            let _ = writeln!(out, "+ {}", self.key_text(key));
+        }
+        for (key, _) in &self.removed {
+            let _ = writeln!(out, "- {}", self.key_text(key));
+        }
+        for (key, changes) in &self.changed {
+            let _ = writeln!(out, "~ {}", self.key_text(key));
+            for change in changes {
+                let _ = writeln!(
+                    out,
+                    "    {}: {:?} -> {:?}",
+                    change.column, change.before, change.after
+                );
+            }
+        }
+        out
+    }
+}
+
+fn pointer(key: &[String], column: Option<&str>) -> String {
+    let escape = |s: &str| s.replace('~', "~0").replace('/', "~1");
+    let mut path = format!("/{}", escape(&key.join(",")));
+    if let Some(column) = column {
+        path.push('/');
+        path.push_str(&escape(column));
+    }
+    path
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff(old: &str, new: &str, keys: &[&str]) -> Result<CsvDiff> {
+        let mut old = Reader::from_reader(old.as_bytes());
+        let old_headers = old.headers()?.clone();
+        let mut new = Reader::from_reader(new.as_bytes());
+        let new_headers = new.headers()?.clone();
+        let keys = keys.iter().map(|k| k.to_string()).collect::<Vec<_>>();
+        diff_csv(old, &old_headers, new, &new_headers, &keys)
+    }
+
+    #[test]
+    fn test_diff_csv() {
+        let old = "id,name,score\n1,alice,10\n2,bob,20\n3,carol,30\n";
+        let new = "id,name,score,team\n1,alice,15,a\n3,carol,30,b\n4,dave,40,c\n";
+        let diff = diff(old, new, &["id"]).unwrap();
+        assert_eq!(diff.columns_added, vec!["team"]);
+        assert_eq!(diff.added.len(), 1);
+        assert_eq!(diff.added[0].0, vec!["4"]);
+        assert_eq!(diff.removed[0].0, vec!["2"]);
+        assert_eq!(
+            diff.changed,
+            vec![(
+                vec!["1".to_string()],
+                vec![CellChange {
+                    column: "score".to_string(),
+                    before: "10".to_string(),
+                    after: "15".to_string(),
+                }]
+            )]
+        );
+
+        let patch = diff.patch();
+        assert_eq!(
+            patch.last().unwrap(),
+            &json!({"op": "replace", "path": "/1/score", "old": "10", "value": "15"})
+        );
+        assert!(diff
+            .summary()
+            .starts_with("added: 1, removed: 1, changed: 1\n"));
+    }
+
+    #[test]
+    fn test_diff_csv_errors() {
+        assert!(diff("id\n1\n", "id\n1\n", &["missing"]).is_err());
+        assert!(diff("id\n1\n1\n", "id\n1\n", &["id"]).is_err());
+    }
+}
@@ -0,0 +1,255 @@
+use super::record::open_record_writer;
+use crate::cli::{CsvWriteOpts, FakeColumn, FakeKind, OutputFormat};
+use anyhow::{anyhow, Result};
+use chrono::{Duration, NaiveDate};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use regex_syntax::hir::{Class, Hir, HirKind};
+use serde_json::{Map, Value};
+
+const FIRST_NAMES: [&str; 24] = [
+    "Alice", "Bruno", "Chiara", "David", "Elena", "Farid", "Giulia", "Hiro", "Ines", "Jonas",
+    "Kiran", "Lucia", "Marco", "Nadia", "Oscar", "Priya", "Quentin", "Rosa", "Samuel", "Tanya",
+    "Umar", "Vera", "Wei", "Yusuf",
+];
+const LAST_NAMES: [&str; 24] = [
+    "Anderson", "Bianchi", "Chen", "Dubois", "Esposito", "Fischer", "Garcia", "Hansen", "Ito",
+    "Jensen", "Kowalski", "Lopez", "Muller", "Novak", "Okafor", "Patel", "Rossi", "Silva",
+    "Tanaka", "Ueda", "Varga", "Wagner", "Yilmaz", "Zhang",
+];
+const EMAIL_DOMAINS: [&str; 3] = ["example.com", "example.org", "example.net"];
+// 正则中 * + {n,} 这类无上限的重复最多额外生成的次数
+const MAX_EXTRA_REPEAT: u32 = 8;
+
+enum Generator {
+    Name,
+    Email,
+    Uuid,
+    Seq,
+    Int(i64, i64),
+    Date(NaiveDate, i64),
+    Enum(Vec<String>),
+    Regex(Hir),
+}
+
+pub fn process_csv_fake(
+    columns: &[FakeColumn],
+    rows: usize,
+    seed: Option<u64>,
+    output: &str,
+    format: OutputFormat,
+    write: &CsvWriteOpts,
+) -> Result<()> {
+    let generators = columns
+        .iter()
+        .map(|column| Generator::new(&column.kind))
+        .collect::<Result<Vec<_>>>()?;
+    let mut rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let mut writer = open_record_writer(format, output, write)?;
+    writer.set_headers(&columns.iter().map(|c| c.name.clone()).collect::<Vec<_>>());
+    for row in 0..rows {
+        let map = columns
+            .iter()
+            .zip(&generators)
+            .map(|(column, generator)| (column.name.clone(), generator.generate(row, &mut rng)))
+            .collect::<Map<_, _>>();
+        writer.write_record(&Value::Object(map))?;
+    }
+    writer.finish()
+}
+
+impl Generator {
+    fn new(kind: &FakeKind) -> Result<Self> {
+        Ok(match kind {
+            FakeKind::Name => Generator::Name,
+            FakeKind::Email => Generator::Email,
+            FakeKind::Uuid => Generator::Uuid,
+            FakeKind::Seq => Generator::Seq,
+            FakeKind::Int(min, max) => Generator::Int(*min, *max),
+            FakeKind::Date(start, end) => Generator::Date(*start, (*end - *start).num_days()),
+            FakeKind::Enum(values) => Generator::Enum(values.clone()),
+            FakeKind::Regex(pattern) => Generator::Regex(
+                regex_syntax::parse(pattern)
+                    .map_err(|e| anyhow!("Invalid regex {:?}: {}", pattern, e))?,
+            ),
+        })
+    }
+
+    fn generate(&self, row: usize, rng: &mut impl Rng) -> Value {
+        match self {
+            Generator::Name => {
+                let (first, last) = random_name(rng);
+                format!("{} {}", first, last).into()
+            }
+            Generator::Email => {
+                let (first, last) = random_name(rng);
+                let domain = EMAIL_DOMAINS.choose(rng).unwrap_or(&EMAIL_DOMAINS[0]);
+                let n = rng.gen_range(1..1000);
+                format!("{}.{}{}@{}", first, last, n, domain)
+                    .to_lowercase()
+                    .into()
+            }
+            Generator::Uuid => random_uuid(rng).into(),
+            Generator::Seq => (row + 1).into(),
+            Generator::Int(min, max) => rng.gen_range(*min..=*max).into(),
+            Generator::Date(start, days) => {
+                let date = *start + Duration::days(rng.gen_range(0..=*days));
+                date.format("%Y-%m-%d").to_string().into()
+            }
+            Generator::Enum(values) => values.choose(rng).cloned().unwrap_or_default().into(),
+            Generator::Regex(hir) => {
+                let mut out = String::new();
+                regex_string(hir, rng, &mut out);
+                out.into()
+            }
+        }
+    }
+}
+
+fn random_name(rng: &mut impl Rng) -> (&'static str, &'static str) {
+    let first = FIRST_NAMES.choose(rng).unwrap_or(&FIRST_NAMES[0]);
+    let last = LAST_NAMES.choose(rng).unwrap_or(&LAST_NAMES[0]);
+    (first, last)
+}
+
+// 随机 uuid v4
+fn random_uuid(rng: &mut impl Rng) -> String {
+    let mut bytes: [u8; 16] = rng.gen();
+    bytes[6] = (bytes[6] & 0x0f) | 0x40;
+    bytes[8] = (bytes[8] & 0x3f) | 0x80;
+    let hex = bytes
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect::<String>();
+    format!(
+        "{}-{}-{}-{}-{}",
+        &hex[..8],
+        &hex[8..12],
+        &hex[12..16],
+        &hex[16..20],
+        &hex[20..]
+    )
+}
+
+// 按正则的语法树生成一个匹配的字符串，忽略 ^ $ \b 这类零宽断言
+fn regex_string(hir: &Hir, rng: &mut impl Rng, out: &mut String) {
+    match hir.kind() {
+        HirKind::Empty | HirKind::Look(_) => {}
+        HirKind::Literal(literal) => out.push_str(&String::from_utf8_lossy(&literal.0)),
+        HirKind::Class(Class::Unicode(class)) => {
+            // \d \w 等在 unicode 模式下包含大量非 ascii 字符，有可打印 ascii 部分时只取这部分
+            let ranges = printable_ascii(class.ranges().iter().map(|r| (r.start(), r.end())));
+            let ranges = if ranges.is_empty() {
+                class
+                    .ranges()
+                    .iter()
+                    .map(|r| (r.start() as u32, r.end() as u32))
+                    .collect()
+            } else {
+                ranges
+            };
+            if let Some(c) = pick_from_ranges(&ranges, rng).and_then(char::from_u32) {
+                out.push(c);
+            }
+        }
+        HirKind::Class(Class::Bytes(class)) => {
+            let ranges = printable_ascii(
+                class
+                    .ranges()
+                    .iter()
+                    .map(|r| (char::from(r.start()), char::from(r.end()))),
+            );
+            if let Some(c) = pick_from_ranges(&ranges, rng).and_then(char::from_u32) {
+                out.push(c);
+            }
+        }
+        HirKind::Repetition(rep) => {
+            let max = rep.max.unwrap_or(rep.min + MAX_EXTRA_REPEAT);
+            for _ in 0..rng.gen_range(rep.min..=max) {
+                regex_string(&rep.sub, rng, out);
+            }
+        }
+        HirKind::Capture(capture) => regex_string(&capture.sub, rng, out),
+        HirKind::Concat(items) => {
+            for item in items {
+                regex_string(item, rng, out);
+            }
+        }
+        HirKind::Alternation(items) => {
+            if let Some(item) = items.choose(rng) {
+                regex_string(item, rng, out);
+            }
+        }
+    }
+}
+
+fn printable_ascii(ranges: impl Iterator<Item = (char, char)>) -> Vec<(u32, u32)> {
+    ranges
+        .map(|(start, end)| ((start as u32).max(0x20), (end as u32).min(0x7e)))
+        .filter(|(start, end)| start <= end)
+        .collect()
+}
+
+// 按区间大小加权随机选择一个码点，区间为闭区间
+fn pick_from_ranges(ranges: &[(u32, u32)], rng: &mut impl Rng) -> Option<u32> {
+    let total = ranges.iter().map(|(s, e)| e - s + 1).sum::<u32>();
+    if total == 0 {
+        return None;
+    }
+    let mut n = rng.gen_range(0..total);
+    for (start, end) in ranges {
+        let size = end - start + 1;
+        if n < size {
+            return Some(start + n);
+        }
+        n -= size;
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use regex::Regex;
+    use serde_json::json;
+
+    fn generate(spec: &str, seed: u64) -> Vec<Value> {
+        let column = spec.parse::<FakeColumn>().unwrap();
+        let generator = Generator::new(&column.kind).unwrap();
+        let mut rng = StdRng::seed_from_u64(seed);
+        (0..50)
+            .map(|row| generator.generate(row, &mut rng))
+            .collect()
+    }
+
+    #[test]
+    fn test_generators() {
+        assert_eq!(generate("id:seq", 1)[..3], [json!(1), json!(2), json!(3)]);
+        assert_eq!(generate("email:email", 1), generate("email:email", 1));
+        assert_ne!(generate("email:email", 1), generate("email:email", 2));
+
+        let email = Regex::new(r"^[a-z]+\.[a-z]+\d+@example\.(com|org|net)$").unwrap();
+        let uuid =
+            Regex::new(r"^[0-9a-f]{8}-[0-9a-f]{4}-4[0-9a-f]{3}-[89ab][0-9a-f]{3}-[0-9a-f]{12}$")
+                .unwrap();
+        let code = Regex::new(r"^[A-Z]{3}-\d{4}(x|y)?$").unwrap();
+        for i in 0..50 {
+            let v = &generate("email:email", 3)[i];
+            assert!(email.is_match(v.as_str().unwrap()), "{}", v);
+            assert!(uuid.is_match(generate("id:uuid", 3)[i].as_str().unwrap()));
+            let v = &generate(r"code:regex:^[A-Z]{3}-\d{4}(x|y)?$", 3)[i];
+            assert!(code.is_match(v.as_str().unwrap()), "{}", v);
+            let n = generate("n:int:-5..5", 3)[i].as_i64().unwrap();
+            assert!((-5..=5).contains(&n));
+            let d = generate("d:date:2024-02-27..2024-03-02", 3)[i].clone();
+            assert!(("2024-02-27".."2024-03-03").contains(&d.as_str().unwrap()));
+            let e = generate("e:enum:a|b", 3)[i].clone();
+            assert!(e == "a" || e == "b");
+        }
+        assert!(Generator::new(&FakeKind::Regex("(".to_string())).is_err());
+    }
+}
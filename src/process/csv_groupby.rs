@@ -0,0 +1,390 @@
+use super::csv_query::compare_cells;
+use super::csv_reader::open_csv;
+use super::record::create_record_writer;
+use super::record::infer::ColumnType;
+use super::{column_index, column_indexes, data_to_output, row_key};
+use crate::cli::{AggFunc, Aggregation, CsvReadOpts, OutputFormat};
+use anyhow::{anyhow, bail, Result};
+use csv::{Reader, StringRecord};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
+
+struct Aggregator {
+    func: AggFunc,
+    count: usize,
+    sum: f64,
+    // 全部为整数时 sum 输出为整数
+    integral: bool,
+    min: Option<String>,
+    max: Option<String>,
+    distinct: HashSet<String>,
+}
+
+// 分组按第一次出现的顺序输出
+struct Groups<T> {
+    index: HashMap<Vec<String>, usize>,
+    groups: Vec<(Vec<String>, T)>,
+}
+
+pub fn process_csv_groupby(
+    input: &str,
+    read: &CsvReadOpts,
+    keys: &[String],
+    aggs: &[Aggregation],
+    output: &str,
+    format: OutputFormat,
+) -> Result<()> {
+    let (reader, headers) = open_csv(input, read)?;
+    let writer = data_to_output(output)?;
+    groupby_csv(reader, &headers, keys, aggs, writer, format)
+}
+
+pub fn process_csv_pivot(
+    input: &str,
+    read: &CsvReadOpts,
+    keys: &[String],
+    column: &str,
+    agg: Aggregation,
+    output: &str,
+    format: OutputFormat,
+) -> Result<()> {
+    let (reader, headers) = open_csv(input, read)?;
+    let writer = data_to_output(output)?;
+    pivot_csv(reader, &headers, keys, column, &agg, writer, format)
+}
+
+fn groupby_csv<'a>(
+    mut reader: Reader<impl Read>,
+    headers: &StringRecord,
+    keys: &[String],
+    aggs: &[Aggregation],
+    writer: Box<dyn Write + 'a>,
+    format: OutputFormat,
+) -> Result<()> {
+    let key_index = column_indexes(headers, keys)?;
+    let agg_index = aggs
+        .iter()
+        .map(|agg| agg_column(headers, agg))
+        .collect::<Result<Vec<_>>>()?;
+
+    let mut groups: Groups<Vec<Aggregator>> = Groups::new();
+    let mut record = StringRecord::new();
+    while reader.read_record(&mut record)? {
+        let aggregators = groups.entry(row_key(&record, &key_index), || {
+            aggs.iter().map(|agg| Aggregator::new(agg.func)).collect()
+        });
+        for (aggregator, index) in aggregators.iter_mut().zip(&agg_index) {
+            push_cell(aggregator, &record, *index, headers)?;
+        }
+    }
+
+    let mut writer = create_record_writer(format, writer)?;
+    for (key, aggregators) in groups.groups {
+        let mut map = key_map(keys, key);
+        for (agg, aggregator) in aggs.iter().zip(aggregators) {
+            map.insert(agg.to_string(), aggregator.finish());
+        }
+        writer.write_record(&Value::Object(map))?;
+    }
+    writer.finish()
+}
+
+// 以 column 列的不同取值作为新列，每个单元格为对应分组的聚合结果
+fn pivot_csv<'a>(
+    mut reader: Reader<impl Read>,
+    headers: &StringRecord,
+    keys: &[String],
+    column: &str,
+    agg: &Aggregation,
+    writer: Box<dyn Write + 'a>,
+    format: OutputFormat,
+) -> Result<()> {
+    let key_index = column_indexes(headers, keys)?;
+    let pivot_index = column_index(headers, column)?;
+    let value_index = agg_column(headers, agg)?;
+
+    let mut groups: Groups<HashMap<String, Aggregator>> = Groups::new();
+    let mut pivot_values = HashSet::new();
+    let mut record = StringRecord::new();
+    while reader.read_record(&mut record)? {
+        let pivot = record.get(pivot_index).unwrap_or_default().to_string();
+        let cells = groups.entry(row_key(&record, &key_index), HashMap::new);
+        let aggregator = cells
+            .entry(pivot.clone())
+            .or_insert_with(|| Aggregator::new(agg.func));
+        push_cell(aggregator, &record, value_index, headers)?;
+        pivot_values.insert(pivot);
+    }
+    let mut pivot_values = pivot_values.into_iter().collect::<Vec<_>>();
+    pivot_values.sort_by(|a, b| compare_cells(a, b));
+
+    // 新列名与 key 列或其他新列重名时加 _pivot 后缀，仍然重名则依次尝试 _pivot2、_pivot3 ...
+    let mut taken = keys.iter().cloned().collect::<HashSet<_>>();
+    let mut names = Vec::new();
+    for pivot in &pivot_values {
+        let name = if pivot.is_empty() { "(empty)" } else { pivot };
+        let mut unique = name.to_string();
+        let mut n = 1;
+        while taken.contains(&unique) {
+            unique = match n {
+                1 => format!("{}_pivot", name),
+                _ => format!("{}_pivot{}", name, n),
+            };
+            n += 1;
+        }
+        taken.insert(unique.clone());
+        names.push(unique);
+    }
+
+    let mut writer = create_record_writer(format, writer)?;
+    for (key, mut cells) in groups.groups {
+        let mut map = key_map(keys, key);
+        for (pivot, name) in pivot_values.iter().zip(&names) {
+            let value = cells.remove(pivot).map_or(Value::Null, Aggregator::finish);
+            map.insert(name.clone(), value);
+        }
+        writer.write_record(&Value::Object(map))?;
+    }
+    writer.finish()
+}
+
+// 只有 count 可以不指定列，此时统计行数
+fn agg_column(headers: &StringRecord, agg: &Aggregation) -> Result<Option<usize>> {
+    match &agg.column {
+        Some(column) => Ok(Some(column_index(headers, column)?)),
+        None if agg.func == AggFunc::Count => Ok(None),
+        None => bail!("Aggregation {} requires a column", agg.func),
+    }
+}
+
+fn push_cell(
+    aggregator: &mut Aggregator,
+    record: &StringRecord,
+    index: Option<usize>,
+    headers: &StringRecord,
+) -> Result<()> {
+    let Some(index) = index else {
+        aggregator.count += 1;
+        return Ok(());
+    };
+    aggregator
+        .push(record.get(index).unwrap_or_default())
+        .map_err(|e| {
+            let line = record.position().map(|p| p.line()).unwrap_or_default();
+            anyhow!("line {}, column {:?}: {}", line, &headers[index], e)
+        })
+}
+
+fn key_map(keys: &[String], key: Vec<String>) -> Map<String, Value> {
+    keys.iter()
+        .cloned()
+        .zip(key.into_iter().map(Value::String))
+        .collect()
+}
+
+impl<T> Groups<T> {
+    fn new() -> Self {
+        Groups {
+            index: HashMap::new(),
+            groups: Vec::new(),
+        }
+    }
+
+    fn entry(&mut self, key: Vec<String>, init: impl FnOnce() -> T) -> &mut T {
+        let i = match self.index.get(&key) {
+            Some(i) => *i,
+            None => {
+                self.index.insert(key.clone(), self.groups.len());
+                self.groups.push((key, init()));
+                self.groups.len() - 1
+            }
+        };
+        &mut self.groups[i].1
+    }
+}
+
+impl Aggregator {
+    fn new(func: AggFunc) -> Self {
+        Aggregator {
+            func,
+            count: 0,
+            sum: 0.0,
+            integral: true,
+            min: None,
+            max: None,
+            distinct: HashSet::new(),
+        }
+    }
+
+    // 空单元格不参与任何聚合
+    fn push(&mut self, cell: &str) -> Result<()> {
+        let cell_type = ColumnType::detect(cell);
+        if cell_type == ColumnType::Null {
+            return Ok(());
+        }
+        self.count += 1;
+        match self.func {
+            AggFunc::Count => {}
+            AggFunc::Sum | AggFunc::Avg => {
+                let Ok(v) = cell.trim().parse::<f64>() else {
+                    bail!("{:?} is not a number", cell);
+                };
+                self.sum += v;
+                self.integral &= cell_type == ColumnType::Integer;
+            }
+            AggFunc::Min => {
+                if self
+                    .min
+                    .as_deref()
+                    .is_none_or(|m| compare_cells(cell, m).is_lt())
+                {
+                    self.min = Some(cell.to_string());
+                }
+            }
+            AggFunc::Max => {
+                if self
+                    .max
+                    .as_deref()
+                    .is_none_or(|m| compare_cells(cell, m).is_gt())
+                {
+                    self.max = Some(cell.to_string());
+                }
+            }
+            AggFunc::Distinct => {
+                self.distinct.insert(cell.to_string());
+            }
+        }
+        Ok(())
+    }
+
+    fn finish(self) -> Value {
+        match self.func {
+            AggFunc::Count => self.count.into(),
+            AggFunc::Distinct => self.distinct.len().into(),
+            _ if self.count == 0 => Value::Null,
+            AggFunc::Sum if self.integral && self.sum.abs() < 9e15 => (self.sum as i64).into(),
+            AggFunc::Sum => self.sum.into(),
+            AggFunc::Avg => (self.sum / self.count as f64).into(),
+            AggFunc::Min => self.min.into(),
+            AggFunc::Max => self.max.into(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DATA: &str = "team,year,player,goals\n\
+        a,2020,x,3\n\
+        a,2021,y,\n\
+        b,2020,z,1.5\n\
+        a,2020,y,4\n\
+        b,2021,z,2\n";
+
+    fn run(f: impl FnOnce(Reader<&[u8]>, &StringRecord, Box<dyn Write + '_>)) -> String {
+        let mut reader = Reader::from_reader(DATA.as_bytes());
+        let headers = reader.headers().unwrap().clone();
+        let mut buf = Vec::new();
+        f(reader, &headers, Box::new(&mut buf));
+        String::from_utf8(buf).unwrap()
+    }
+
+    fn aggs(specs: &[&str]) -> Vec<Aggregation> {
+        specs.iter().map(|s| s.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_groupby_csv() {
+        let aggs = aggs(&[
+            "count",
+            "count:goals",
+            "sum:goals",
+            "avg:goals",
+            "min:year",
+            "max:player",
+            "distinct:player",
+        ]);
+        let out = run(|reader, headers, writer| {
+            let keys = vec!["team".to_string()];
+            groupby_csv(reader, headers, &keys, &aggs, writer, OutputFormat::Csv).unwrap()
+        });
+        assert_eq!(
+            out,
+            "team,count,count_goals,sum_goals,avg_goals,min_year,max_player,distinct_player\n\
+             a,3,2,7,3.5,2020,y,2\n\
+             b,2,2,3.5,1.75,2020,z,1\n"
+        );
+
+        let mut reader = Reader::from_reader("k,v\n1,abc\n".as_bytes());
+        let headers = reader.headers().unwrap().clone();
+        let err = groupby_csv(
+            reader,
+            &headers,
+            &["k".to_string()],
+            &self::aggs(&["sum:v"]),
+            Box::new(std::io::sink()),
+            OutputFormat::Csv,
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "line 2, column \"v\": \"abc\" is not a number"
+        );
+    }
+
+    #[test]
+    fn test_pivot_csv() {
+        let out = run(|reader, headers, writer| {
+            let keys = vec!["team".to_string()];
+            let agg = "sum:goals".parse().unwrap();
+            pivot_csv(
+                reader,
+                headers,
+                &keys,
+                "year",
+                &agg,
+                writer,
+                OutputFormat::Csv,
+            )
+            .unwrap()
+        });
+        assert_eq!(out, "team,2020,2021\na,7,\nb,1.5,2\n");
+
+        let out = run(|reader, headers, writer| {
+            let keys = vec!["player".to_string()];
+            let agg = "count".parse().unwrap();
+            pivot_csv(
+                reader,
+                headers,
+                &keys,
+                "team",
+                &agg,
+                writer,
+                OutputFormat::Csv,
+            )
+            .unwrap()
+        });
+        assert_eq!(out, "player,a,b\nx,1,\ny,2,\nz,,2\n");
+
+        // 取值与 key 列或空值占位名相同时不能覆盖已有列
+        let mut reader = Reader::from_reader("k,p\na,k\na,\na,(empty)\nb,k\n".as_bytes());
+        let headers = reader.headers().unwrap().clone();
+        let mut buf = Vec::new();
+        pivot_csv(
+            reader,
+            &headers,
+            &["k".to_string()],
+            "p",
+            &"count".parse().unwrap(),
+            Box::new(&mut buf),
+            OutputFormat::Csv,
+        )
+        .unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "k,(empty),(empty)_pivot,k_pivot\na,1,1,1\nb,,,1\n"
+        );
+    }
+}
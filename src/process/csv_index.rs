@@ -0,0 +1,280 @@
+use super::csv_reader::{detect_encoding, resolve_dialect, Dialect};
+use super::spreadsheet::is_spreadsheet;
+use crate::cli::CsvReadOpts;
+use anyhow::{bail, Result};
+use csv::{ByteRecord, Reader, ReaderBuilder, StringRecord};
+use encoding_rs::UTF_8;
+use std::fs::File;
+use std::io::{BufReader, Read, Seek, SeekFrom};
+use std::time::UNIX_EPOCH;
+
+const INDEX_MAGIC: &[u8; 8] = b"RCSVIDX1";
+const SAMPLE_BYTES: u64 = 64 * 1024;
+
+// 每隔 every 行记录一次数据行在文件中的字节偏移，offsets[i] 对应第 i * every + 1 行
+#[derive(Debug, PartialEq)]
+pub struct CsvIndex {
+    // 建立索引时源文件的长度和修改时间，任何一个不一致都视为索引过期
+    len: u64,
+    modified: u64,
+    delimiter: u8,
+    header: bool,
+    every: u64,
+    rows: u64,
+    offsets: Vec<u64>,
+}
+
+pub struct IndexedReader {
+    pub reader: Reader<Box<dyn Read>>,
+    pub headers: StringRecord,
+    // reader 第一行的行号，从 1 开始
+    pub first: usize,
+}
+
+pub fn process_csv_index(input: &str, read: &CsvReadOpts, every: u64) -> Result<()> {
+    if input == "-" || is_spreadsheet(input) {
+        bail!("csv index requires a CSV file, not {:?}", input);
+    }
+    let Some(dialect) = read_dialect(input, read)? else {
+        bail!("csv index only supports UTF-8 input");
+    };
+    if dialect.quote != b'"' {
+        bail!("csv index only supports double-quoted input");
+    }
+    let file = File::open(input)?;
+
+    let (len, modified) = file_stamp(input)?;
+    let mut index = CsvIndex {
+        len,
+        modified,
+        delimiter: dialect.delimiter,
+        header: dialect.header,
+        every: every.max(1),
+        rows: 0,
+        offsets: Vec::new(),
+    };
+    index.build(BufReader::new(file))?;
+    std::fs::write(index_path(input), index.to_bytes())?;
+    println!("Indexed {} rows into {}", index.rows, index_path(input));
+    Ok(())
+}
+
+// 读取与 input 对应的索引文件，文件不存在、已过期或与本次读取选项解析出的方言不符时返回 None，
+// 由调用方按普通方式读取
+pub fn load_index(input: &str, read: &CsvReadOpts) -> Result<Option<CsvIndex>> {
+    if input == "-" || is_spreadsheet(input) {
+        return Ok(None);
+    }
+    let Ok(data) = std::fs::read(index_path(input)) else {
+        return Ok(None);
+    };
+    let index = CsvIndex::from_bytes(&data)?;
+    if (index.len, index.modified) != file_stamp(input)? {
+        return Ok(None);
+    }
+    let matches = read_dialect(input, read)?.is_some_and(|dialect| {
+        (dialect.delimiter, dialect.quote, dialect.header) == (index.delimiter, b'"', index.header)
+    });
+    Ok(matches.then_some(index))
+}
+
+// 偏移量按原始字节计算，转码后的位置无法在原文件中定位，因此非 UTF-8 输入返回 None
+fn read_dialect(input: &str, read: &CsvReadOpts) -> Result<Option<Dialect>> {
+    let mut sample = Vec::new();
+    File::open(input)?
+        .take(SAMPLE_BYTES)
+        .read_to_end(&mut sample)?;
+    if read.encoding.unwrap_or_else(|| detect_encoding(&sample)) != UTF_8 {
+        return Ok(None);
+    }
+    Ok(Some(resolve_dialect(&sample, read)))
+}
+
+fn index_path(input: &str) -> String {
+    format!("{}.idx", input)
+}
+
+fn file_stamp(input: &str) -> Result<(u64, u64)> {
+    let meta = std::fs::metadata(input)?;
+    let modified = meta.modified()?.duration_since(UNIX_EPOCH)?.as_nanos() as u64;
+    Ok((meta.len(), modified))
+}
+
+impl CsvIndex {
+    pub fn rows(&self) -> usize {
+        self.rows as usize
+    }
+
+    fn build(&mut self, reader: impl Read) -> Result<()> {
+        let mut reader = self.reader_builder().from_reader(reader);
+        let mut record = ByteRecord::new();
+        while reader.read_byte_record(&mut record)? {
+            if self.rows.is_multiple_of(self.every) {
+                let offset = record.position().map(|p| p.byte()).unwrap_or_default();
+                self.offsets.push(offset);
+            }
+            self.rows += 1;
+        }
+        Ok(())
+    }
+
+    // 定位到不晚于 row 的最近索引行
+    pub fn seek(&self, input: &str, row: usize) -> Result<IndexedReader> {
+        let mut file = File::open(input)?;
+        let mut first = ReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .has_headers(false)
+            .from_reader(file.by_ref());
+        let mut record = StringRecord::new();
+        first.read_record(&mut record)?;
+        let headers = if self.header {
+            record
+                .iter()
+                .enumerate()
+                .map(|(i, h)| {
+                    if i == 0 {
+                        h.trim_start_matches('\u{feff}')
+                    } else {
+                        h
+                    }
+                })
+                .collect()
+        } else {
+            (1..=record.len()).map(|i| format!("col_{}", i)).collect()
+        };
+
+        let slot = (row.saturating_sub(1) as u64 / self.every) as usize;
+        let (offset, first_row) = match self.offsets.get(slot) {
+            Some(offset) => (*offset, slot as u64 * self.every + 1),
+            // 超出索引范围时定位到文件末尾，之后读不到任何行
+            None => (self.len, self.rows + 1),
+        };
+        file.seek(SeekFrom::Start(offset))?;
+        let reader: Box<dyn Read> = Box::new(BufReader::new(file));
+        let reader = ReaderBuilder::new()
+            .delimiter(self.delimiter)
+            .has_headers(false)
+            .from_reader(reader);
+        Ok(IndexedReader {
+            reader,
+            headers,
+            first: first_row as usize,
+        })
+    }
+
+    fn reader_builder(&self) -> ReaderBuilder {
+        let mut builder = ReaderBuilder::new();
+        builder.delimiter(self.delimiter).has_headers(self.header);
+        builder
+    }
+
+    fn to_bytes(&self) -> Vec<u8> {
+        let mut buf = INDEX_MAGIC.to_vec();
+        for v in [self.len, self.modified, self.every, self.rows] {
+            buf.extend_from_slice(&v.to_le_bytes());
+        }
+        buf.push(self.delimiter);
+        buf.push(self.header as u8);
+        for offset in &self.offsets {
+            buf.extend_from_slice(&offset.to_le_bytes());
+        }
+        buf
+    }
+
+    fn from_bytes(data: &[u8]) -> Result<Self> {
+        let Some(body) = data.strip_prefix(INDEX_MAGIC) else {
+            bail!("Invalid csv index file");
+        };
+        if body.len() < 34 || (body.len() - 34) % 8 != 0 {
+            bail!("Invalid csv index file");
+        }
+        let u64_at = |i: usize| u64::from_le_bytes(body[i..i + 8].try_into().unwrap());
+        Ok(CsvIndex {
+            len: u64_at(0),
+            modified: u64_at(8),
+            every: u64_at(16),
+            rows: u64_at(24),
+            delimiter: body[32],
+            header: body[33] != 0,
+            offsets: (34..body.len()).step_by(8).map(u64_at).collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_index() {
+        let path = std::env::temp_dir().join(format!("rcli-index-{}.csv", std::process::id()));
+        std::fs::write(&path, "\u{feff}n;v\n1;a\n2;\"b\nb\"\n3;c\n4;d\n5;e\n").unwrap();
+        let input = path.to_str().unwrap();
+        let (len, modified) = file_stamp(input).unwrap();
+        let mut index = CsvIndex {
+            len,
+            modified,
+            delimiter: b';',
+            header: true,
+            every: 2,
+            rows: 0,
+            offsets: Vec::new(),
+        };
+        index.build(File::open(input).unwrap()).unwrap();
+        assert_eq!(index.rows(), 5);
+        assert_eq!(index.offsets.len(), 3);
+        assert_eq!(CsvIndex::from_bytes(&index.to_bytes()).unwrap(), index);
+
+        let indexed = index.seek(input, 4).unwrap();
+        assert_eq!(indexed.headers, StringRecord::from(vec!["n", "v"]));
+        assert_eq!(indexed.first, 3);
+        let rows = indexed
+            .reader
+            .into_records()
+            .map(|r| r.unwrap().iter().collect::<Vec<_>>().join(":"))
+            .collect::<Vec<_>>();
+        assert_eq!(rows, vec!["3:c", "4:d", "5:e"]);
+
+        let indexed = index.seek(input, 9).unwrap();
+        assert_eq!(indexed.first, 6);
+        assert_eq!(indexed.reader.into_records().count(), 0);
+        assert!(CsvIndex::from_bytes(b"RCSVIDX1").is_err());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_load_index_dialect_mismatch() {
+        let path = std::env::temp_dir().join(format!("rcli-index-opts-{}.csv", std::process::id()));
+        std::fs::write(&path, "n,v\n1,a\n2,b\n").unwrap();
+        let input = path.to_str().unwrap();
+        let read = CsvReadOpts {
+            delimiter: b',',
+            header: true,
+            sniff: false,
+            encoding: None,
+            sheet: None,
+        };
+        process_csv_index(input, &read, 1).unwrap();
+        assert!(load_index(input, &read).unwrap().is_some());
+        // 与建立索引时不同的表头、分隔符或编码设置都不使用索引
+        let mismatched = [
+            CsvReadOpts {
+                header: false,
+                ..read.clone()
+            },
+            CsvReadOpts {
+                delimiter: b';',
+                ..read.clone()
+            },
+            CsvReadOpts {
+                encoding: encoding_rs::Encoding::for_label(b"latin1"),
+                ..read.clone()
+            },
+        ];
+        for read in &mismatched {
+            assert!(load_index(input, read).unwrap().is_none());
+        }
+        std::fs::remove_file(&path).unwrap();
+        std::fs::remove_file(index_path(input)).unwrap();
+    }
+}
@@ -0,0 +1,175 @@
+use super::csv_reader::open_csv;
+use super::record::create_record_writer;
+use super::{column_indexes, data_to_output, row_key};
+use crate::cli::{CsvReadOpts, JoinKind, OutputFormat};
+use anyhow::Result;
+use csv::{Reader, StringRecord};
+use serde_json::{Map, Value};
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
+
+pub fn process_csv_join(
+    left: &str,
+    right: &str,
+    keys: &[String],
+    kind: JoinKind,
+    read: &CsvReadOpts,
+    output: &str,
+    format: OutputFormat,
+) -> Result<()> {
+    let (left_reader, left_headers) = open_csv(left, read)?;
+    let (right_reader, right_headers) = open_csv(right, read)?;
+    let writer = data_to_output(output)?;
+    join_csv(
+        (left_reader, &left_headers),
+        (right_reader, &right_headers),
+        keys,
+        kind,
+        writer,
+        format,
+    )
+}
+
+fn join_csv<'a>(
+    (mut left, left_headers): (Reader<impl Read>, &StringRecord),
+    (right, right_headers): (Reader<impl Read>, &StringRecord),
+    keys: &[String],
+    kind: JoinKind,
+    writer: Box<dyn Write + 'a>,
+    format: OutputFormat,
+) -> Result<()> {
+    let left_keys = column_indexes(left_headers, keys)?;
+    let right_keys = column_indexes(right_headers, keys)?;
+
+    // 右表的非 key 列，与已有列重名时加 _right 后缀，仍然重名则依次尝试 _right2、_right3 ...
+    let mut taken = left_headers
+        .iter()
+        .map(String::from)
+        .collect::<HashSet<_>>();
+    let mut right_columns = Vec::new();
+    for (i, name) in right_headers.iter().enumerate() {
+        if right_keys.contains(&i) {
+            continue;
+        }
+        let mut unique = name.to_string();
+        let mut n = 1;
+        while taken.contains(&unique) {
+            unique = match n {
+                1 => format!("{}_right", name),
+                _ => format!("{}_right{}", name, n),
+            };
+            n += 1;
+        }
+        taken.insert(unique.clone());
+        right_columns.push((i, unique));
+    }
+
+    // 右表按 key 建立索引，左表流式读取
+    let mut right_rows: HashMap<Vec<String>, Vec<StringRecord>> = HashMap::new();
+    let mut right_order = Vec::new();
+    for record in right.into_records() {
+        let record = record?;
+        let key = row_key(&record, &right_keys);
+        if !right_rows.contains_key(&key) {
+            right_order.push(key.clone());
+        }
+        right_rows.entry(key).or_default().push(record);
+    }
+
+    let joined = |left: Option<&StringRecord>, right: Option<&StringRecord>, key: &[String]| {
+        let mut map = Map::new();
+        for (i, name) in left_headers.iter().enumerate() {
+            let value = match (left, left_keys.iter().position(|k| *k == i)) {
+                (Some(record), _) => record.get(i).unwrap_or_default(),
+                (None, Some(k)) => key[k].as_str(),
+                (None, None) => "",
+            };
+            map.insert(name.to_string(), Value::String(value.to_string()));
+        }
+        for (i, name) in &right_columns {
+            let value = right.and_then(|r| r.get(*i)).unwrap_or_default();
+            map.insert(name.clone(), Value::String(value.to_string()));
+        }
+        Value::Object(map)
+    };
+
+    let mut writer = create_record_writer(format, writer)?;
+    let mut matched = HashSet::new();
+    let mut record = StringRecord::new();
+    while left.read_record(&mut record)? {
+        let key = row_key(&record, &left_keys);
+        match right_rows.get(&key) {
+            Some(rows) => {
+                for row in rows {
+                    writer.write_record(&joined(Some(&record), Some(row), &key))?;
+                }
+                if let JoinKind::Full = kind {
+                    matched.insert(key);
+                }
+            }
+            None => {
+                if let JoinKind::Left | JoinKind::Full = kind {
+                    writer.write_record(&joined(Some(&record), None, &key))?;
+                }
+            }
+        }
+    }
+    if let JoinKind::Full = kind {
+        for key in right_order.iter().filter(|k| !matched.contains(*k)) {
+            for row in &right_rows[key] {
+                writer.write_record(&joined(None, Some(row), key))?;
+            }
+        }
+    }
+    writer.finish()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEFT: &str = "id,name\n1,alice\n2,bob\n";
+    const RIGHT: &str = "id,name,team\n1,Alice,a\n1,Alice2,b\n3,Carol,c\n";
+
+    fn join(left: &str, right: &str, kind: JoinKind) -> String {
+        let mut left = Reader::from_reader(left.as_bytes());
+        let left_headers = left.headers().unwrap().clone();
+        let mut right = Reader::from_reader(right.as_bytes());
+        let right_headers = right.headers().unwrap().clone();
+        let mut buf = Vec::new();
+        join_csv(
+            (left, &left_headers),
+            (right, &right_headers),
+            &["id".to_string()],
+            kind,
+            Box::new(&mut buf),
+            OutputFormat::Csv,
+        )
+        .unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn test_join_csv() {
+        assert_eq!(
+            join(LEFT, RIGHT, JoinKind::Inner),
+            "id,name,name_right,team\n1,alice,Alice,a\n1,alice,Alice2,b\n"
+        );
+        assert_eq!(
+            join(LEFT, RIGHT, JoinKind::Left),
+            "id,name,name_right,team\n1,alice,Alice,a\n1,alice,Alice2,b\n2,bob,,\n"
+        );
+        assert_eq!(
+            join(LEFT, RIGHT, JoinKind::Full),
+            "id,name,name_right,team\n1,alice,Alice,a\n1,alice,Alice2,b\n2,bob,,\n3,,Carol,c\n"
+        );
+        assert_eq!(
+            join(
+                "id,name,name_right\n1,a,b\n",
+                "id,name,name_right\n1,c,d\n",
+                JoinKind::Inner
+            ),
+            "id,name,name_right,name_right2,name_right_right\n1,a,b,c,d\n"
+        );
+    }
+}
@@ -0,0 +1,348 @@
+use super::csv_reader::read_csv_text;
+use super::data_to_output;
+use super::record::create_record_writer;
+use crate::cli::{CsvReadOpts, OutputFormat};
+use anyhow::{bail, Result};
+use csv::WriterBuilder;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::fmt::Write as _;
+use std::io::Write;
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct LintProblem {
+    pub line: u64,
+    pub kind: &'static str,
+    pub message: String,
+}
+
+// 按原始文本解析出的一条记录，line 为记录开始的行号
+#[derive(Debug, PartialEq)]
+struct RawRecord {
+    line: u64,
+    fields: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    FieldStart,
+    Unquoted,
+    // 记录引号开始的行号，文件结束仍未闭合时用于报告
+    Quoted(u64),
+    AfterQuote,
+}
+
+pub fn process_csv_lint(
+    input: &str,
+    read: &CsvReadOpts,
+    output: &str,
+    format: Option<OutputFormat>,
+    fix: Option<&str>,
+    fix_delimiter: u8,
+) -> Result<()> {
+    let (text, dialect) = read_csv_text(input, read)?;
+    let (records, mut problems) = scan(&text, dialect.delimiter as char, dialect.quote as char);
+    problems.extend(check_records(&records, dialect.header));
+    problems.sort_by_key(|p| p.line);
+
+    let mut writer = data_to_output(output)?;
+    match format {
+        Some(format) => {
+            let mut writer = create_record_writer(format, writer)?;
+            for problem in &problems {
+                writer.write_record(&serde_json::to_value(problem)?)?;
+            }
+            writer.finish()?;
+        }
+        None => {
+            writer.write_all(lint_report(&problems).as_bytes())?;
+            writer.flush()?;
+        }
+    }
+
+    match fix {
+        Some(fix) => {
+            let fixed = fix_records(records, dialect.header);
+            write_fixed(&fixed, data_to_output(fix)?, fix_delimiter)
+        }
+        None if !problems.is_empty() => {
+            bail!("CSV lint found {} problem(s)", problems.len())
+        }
+        None => Ok(()),
+    }
+}
+
+// 逐字符扫描，和 csv crate 不同的是遇到问题不会中断，而是记录下来继续解析
+fn scan(text: &str, delimiter: char, quote: char) -> (Vec<RawRecord>, Vec<LintProblem>) {
+    let mut records = Vec::new();
+    let mut problems = Vec::new();
+    let mut fields = Vec::new();
+    let mut field = String::new();
+    let mut state = State::FieldStart;
+    let mut line = 1;
+    let mut record_line = 1;
+    let mut chars = text.chars().peekable();
+
+    while let Some(c) = chars.next() {
+        if let State::Quoted(_) = state {
+            if c == quote {
+                if chars.peek() == Some(&quote) {
+                    chars.next();
+                    field.push(quote);
+                } else {
+                    state = State::AfterQuote;
+                }
+            } else {
+                if c == '\n' {
+                    line += 1;
+                }
+                field.push(c);
+            }
+            continue;
+        }
+
+        if c == delimiter {
+            fields.push(std::mem::take(&mut field));
+            state = State::FieldStart;
+        } else if c == '\n' {
+            // 空行直接跳过，与 csv crate 的行为一致
+            if !(fields.is_empty() && field.is_empty() && state == State::FieldStart) {
+                fields.push(std::mem::take(&mut field));
+                records.push(RawRecord {
+                    line: record_line,
+                    fields: std::mem::take(&mut fields),
+                });
+            }
+            state = State::FieldStart;
+            line += 1;
+            record_line = line;
+        } else if c == '\r' {
+            if chars.peek() != Some(&'\n') {
+                problems.push(LintProblem {
+                    line,
+                    kind: "stray_cr",
+                    message: "stray carriage return".to_string(),
+                });
+            }
+        } else if c == quote && state == State::FieldStart {
+            state = State::Quoted(line);
+        } else {
+            if c == quote && state == State::Unquoted {
+                problems.push(LintProblem {
+                    line,
+                    kind: "quote",
+                    message: format!("quote in unquoted field {}", fields.len() + 1),
+                });
+            } else if state == State::AfterQuote {
+                problems.push(LintProblem {
+                    line,
+                    kind: "quote",
+                    message: format!("unexpected {:?} after closing quote", c),
+                });
+            }
+            field.push(c);
+            state = State::Unquoted;
+        }
+    }
+
+    if let State::Quoted(open) = state {
+        problems.push(LintProblem {
+            line: open,
+            kind: "unbalanced_quote",
+            message: "quote is never closed".to_string(),
+        });
+    }
+    if !(fields.is_empty() && field.is_empty() && state == State::FieldStart) {
+        fields.push(field);
+        records.push(RawRecord {
+            line: record_line,
+            fields,
+        });
+    }
+    (records, problems)
+}
+
+fn check_records(records: &[RawRecord], header: bool) -> Vec<LintProblem> {
+    let mut problems = Vec::new();
+    let Some(first) = records.first() else {
+        return problems;
+    };
+    let expected = first.fields.len();
+    let problem = |line, kind, message| LintProblem {
+        line,
+        kind,
+        message,
+    };
+
+    if header {
+        let mut seen = HashMap::new();
+        for (i, name) in first.fields.iter().enumerate() {
+            if name.trim().is_empty() {
+                if first.fields[i..].iter().all(|n| n.trim().is_empty()) {
+                    problems.push(problem(
+                        first.line,
+                        "trailing_empty_column",
+                        format!("header column {} is empty at the end of the row", i + 1),
+                    ));
+                    break;
+                }
+                problems.push(problem(
+                    first.line,
+                    "empty_header",
+                    format!("header column {} has no name", i + 1),
+                ));
+            } else if let Some(prev) = seen.insert(name.as_str(), i) {
+                problems.push(problem(
+                    first.line,
+                    "duplicate_header",
+                    format!(
+                        "duplicate header {:?} in columns {} and {}",
+                        name,
+                        prev + 1,
+                        i + 1
+                    ),
+                ));
+            }
+        }
+    }
+
+    for record in &records[1..] {
+        let found = record.fields.len();
+        if found == expected {
+            continue;
+        }
+        // 多出来的列全部为空，通常是行尾多了分隔符
+        if found > expected && record.fields[expected..].iter().all(String::is_empty) {
+            problems.push(problem(
+                record.line,
+                "trailing_empty_column",
+                format!("{} trailing empty field(s)", found - expected),
+            ));
+        } else {
+            problems.push(problem(
+                record.line,
+                "field_count",
+                format!("expected {} fields, found {}", expected, found),
+            ));
+        }
+    }
+    problems
+}
+
+// 修复规则：表头重复或为空的列重新命名，去掉行尾多余的空列，列数不足时补空值，
+// 仍有多出数据的行在表头追加 col_n 列
+fn fix_records(records: Vec<RawRecord>, header: bool) -> Vec<Vec<String>> {
+    let mut rows = records.into_iter().map(|r| r.fields).collect::<Vec<_>>();
+    let Some(first) = rows.first() else {
+        return rows;
+    };
+    let mut width = first.len();
+    if header {
+        while width > 0 && rows[0][width - 1].trim().is_empty() {
+            width -= 1;
+        }
+        rows[0].truncate(width);
+    }
+    for row in rows.iter_mut().skip(1) {
+        while row.len() > width && row.last().is_some_and(String::is_empty) {
+            row.pop();
+        }
+    }
+    let width = rows.iter().map(Vec::len).max().unwrap_or(width).max(width);
+
+    if header {
+        let mut seen = HashMap::new();
+        let names = &mut rows[0];
+        names.resize(width, String::new());
+        for (i, name) in names.iter_mut().enumerate() {
+            if name.trim().is_empty() {
+                *name = format!("col_{}", i + 1);
+            }
+            let count = seen.entry(name.clone()).or_insert(0);
+            *count += 1;
+            if *count > 1 {
+                *name = format!("{}_{}", name, count);
+            }
+        }
+    }
+    for row in rows.iter_mut() {
+        row.resize(width, String::new());
+    }
+    rows
+}
+
+fn write_fixed(rows: &[Vec<String>], writer: impl Write, delimiter: u8) -> Result<()> {
+    let mut writer = WriterBuilder::new()
+        .delimiter(delimiter)
+        .from_writer(writer);
+    for row in rows {
+        writer.write_record(row)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn lint_report(problems: &[LintProblem]) -> String {
+    let mut out = String::new();
+    for p in problems {
+        let _ = writeln!(out, "line {} [{}]: {}", p.line, p.kind, p.message);
+    }
+    if problems.is_empty() {
+        out.push_str("ok\n");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lint(text: &str) -> (Vec<RawRecord>, Vec<(u64, &'static str)>) {
+        let (records, mut problems) = scan(text, ',', '"');
+        problems.extend(check_records(&records, true));
+        problems.sort_by_key(|p| p.line);
+        let summary = problems.iter().map(|p| (p.line, p.kind)).collect();
+        (records, summary)
+    }
+
+    #[test]
+    fn test_lint_problems() {
+        let text = "id,name,id,\n1,a,2\n2,b\"c,3\n\n3,\"x\ny\",4,,\r\n4,c\rd,5,e\n5,\"z";
+        let (records, problems) = lint(text);
+        assert_eq!(
+            records.iter().map(|r| r.line).collect::<Vec<_>>(),
+            vec![1, 2, 3, 5, 7, 8]
+        );
+        assert_eq!(records[3].fields, vec!["3", "x\ny", "4", "", ""]);
+        assert_eq!(
+            problems,
+            vec![
+                (1, "duplicate_header"),
+                (1, "trailing_empty_column"),
+                (2, "field_count"),
+                (3, "quote"),
+                (3, "field_count"),
+                (5, "trailing_empty_column"),
+                (7, "stray_cr"),
+                (8, "unbalanced_quote"),
+                (8, "field_count"),
+            ]
+        );
+
+        let (_, problems) = lint("a,b\r\n1,\"2\"\r\n");
+        assert!(problems.is_empty());
+        assert!(lint_report(&[]).starts_with("ok"));
+    }
+
+    #[test]
+    fn test_fix_records() {
+        let (records, _) = scan("id,id,,\n1,2,,\n3\n4,5,6\n", ',', '"');
+        let rows = fix_records(records, true);
+        let mut buf = Vec::new();
+        write_fixed(&rows, &mut buf, b';').unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "id;id_2;col_3\n1;2;\n3;;\n4;5;6\n"
+        );
+    }
+}
@@ -0,0 +1,219 @@
+use super::csv_reader::open_csv;
+use super::data_to_output;
+use super::record::create_record_writer;
+use super::text::{create_signer, TextSign};
+use crate::cli::{CryptFormat, CsvReadOpts, MaskMode, MaskRule, OutputFormat};
+use anyhow::{anyhow, bail, Result};
+use csv::{Reader, StringRecord};
+use serde_json::{Map, Value};
+use std::io::{Read, Write};
+
+// blake3 签名为 32 字节，base64 编码后 43 个字符，假名最长只能截取到这里
+const MAX_LENGTH: usize = 43;
+
+// 同一个 key 对同一个值总是得到同样的假名，不同文件之间仍然可以 join
+struct Masker {
+    signer: Box<dyn TextSign>,
+    length: usize,
+}
+
+pub fn process_csv_mask(
+    input: &str,
+    read: &CsvReadOpts,
+    key: &str,
+    rules: &[MaskRule],
+    length: usize,
+    output: &str,
+    format: OutputFormat,
+) -> Result<()> {
+    let masker = Masker::new(std::fs::read(key)?, length)?;
+    let (reader, headers) = open_csv(input, read)?;
+    let writer = data_to_output(output)?;
+    mask_csv(reader, &headers, &masker, rules, writer, format)
+}
+
+fn mask_csv<'a>(
+    mut reader: Reader<impl Read>,
+    headers: &StringRecord,
+    masker: &Masker,
+    rules: &[MaskRule],
+    writer: Box<dyn Write + 'a>,
+    format: OutputFormat,
+) -> Result<()> {
+    let mut modes = vec![None; headers.len()];
+    for rule in rules {
+        let index = headers
+            .iter()
+            .position(|h| h == rule.column)
+            .ok_or_else(|| anyhow!("Column {:?} not found", rule.column))?;
+        modes[index] = Some(rule.mode);
+    }
+
+    let mut writer = create_record_writer(format, writer)?;
+    let mut record = StringRecord::new();
+    while reader.read_record(&mut record)? {
+        let mut map = Map::new();
+        for (i, name) in headers.iter().enumerate() {
+            let value = record.get(i).unwrap_or_default();
+            let value = match modes[i] {
+                Some(mode) => masker.mask(value, mode)?,
+                None => value.to_string(),
+            };
+            map.insert(name.to_string(), Value::String(value));
+        }
+        writer.write_record(&Value::Object(map))?;
+    }
+    writer.finish()
+}
+
+impl Masker {
+    fn new(key: Vec<u8>, length: usize) -> Result<Self> {
+        if !(1..=MAX_LENGTH).contains(&length) {
+            bail!(
+                "--length must be between 1 and {}, got {}",
+                MAX_LENGTH,
+                length
+            );
+        }
+        Ok(Masker {
+            signer: create_signer(CryptFormat::Blake, key)?,
+            length,
+        })
+    }
+
+    fn mask(&self, value: &str, mode: MaskMode) -> Result<String> {
+        // 空值保持为空，避免所有空单元格变成同一个假名
+        if value.is_empty() {
+            return Ok(String::new());
+        }
+        match mode {
+            MaskMode::Hash => self.pseudonym(value),
+            MaskMode::Email => match value.rsplit_once('@') {
+                Some((local, domain)) => Ok(format!("{}@{}", self.pseudonym(local)?, domain)),
+                None => self.pseudonym(value),
+            },
+            MaskMode::Last(keep) => Ok(keep_last_digits(value, keep)),
+        }
+    }
+
+    fn pseudonym(&self, value: &str) -> Result<String> {
+        let mut hash = self.signer.sign(&mut value.as_bytes())?;
+        hash.truncate(self.length);
+        Ok(hash)
+    }
+}
+
+// 只保留最后 keep 位数字，其余数字替换为 *，分隔符等非数字字符保持不变
+fn keep_last_digits(value: &str, keep: usize) -> String {
+    let digits = value.chars().filter(char::is_ascii_digit).count();
+    // 数字总数不超过 keep 时全部隐藏，否则等于没有脱敏
+    let hidden = if digits > keep { digits - keep } else { digits };
+    let mut seen = 0;
+    value
+        .chars()
+        .map(|c| {
+            if !c.is_ascii_digit() {
+                return c;
+            }
+            seen += 1;
+            if seen <= hidden {
+                '*'
+            } else {
+                c
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn masker() -> Masker {
+        Masker::new(vec![7; 32], 16).unwrap()
+    }
+
+    #[test]
+    fn test_mask_modes() {
+        let masker = masker();
+        let hash = masker.mask("alice", MaskMode::Hash).unwrap();
+        assert_eq!(hash.len(), 16);
+        assert_eq!(hash, masker.mask("alice", MaskMode::Hash).unwrap());
+        assert_ne!(hash, masker.mask("bob", MaskMode::Hash).unwrap());
+        assert_ne!(
+            hash,
+            Masker::new(vec![8; 32], 16)
+                .unwrap()
+                .mask("alice", MaskMode::Hash)
+                .unwrap()
+        );
+
+        let email = masker.mask("alice@example.com", MaskMode::Email).unwrap();
+        assert_eq!(email, format!("{}@example.com", hash));
+        assert_eq!(masker.mask("", MaskMode::Email).unwrap(), "");
+
+        assert_eq!(
+            masker.mask("555-123-4567", MaskMode::Last(4)).unwrap(),
+            "***-***-4567"
+        );
+        assert_eq!(masker.mask("123", MaskMode::Last(4)).unwrap(), "***");
+        assert!(Masker::new(vec![7; 16], 16).is_err());
+        assert!(Masker::new(vec![7; 32], 0).is_err());
+        assert!(Masker::new(vec![7; 32], MAX_LENGTH + 1).is_err());
+        let longest = Masker::new(vec![7; 32], MAX_LENGTH).unwrap();
+        assert_eq!(
+            longest.mask("alice", MaskMode::Hash).unwrap().len(),
+            MAX_LENGTH
+        );
+    }
+
+    #[test]
+    fn test_mask_csv() {
+        let data = "id,email,phone\n1,a@x.com,5551234\n2,,99\n";
+        let mut reader = Reader::from_reader(data.as_bytes());
+        let headers = reader.headers().unwrap().clone();
+        let rules = vec![
+            MaskRule {
+                column: "email".to_string(),
+                mode: MaskMode::Email,
+            },
+            MaskRule {
+                column: "phone".to_string(),
+                mode: MaskMode::Last(2),
+            },
+        ];
+        let masker = masker();
+        let mut buf = Vec::new();
+        mask_csv(
+            reader,
+            &headers,
+            &masker,
+            &rules,
+            Box::new(&mut buf),
+            OutputFormat::Csv,
+        )
+        .unwrap();
+        let expected = format!(
+            "id,email,phone\n1,{}@x.com,*****34\n2,,**\n",
+            masker.pseudonym("a").unwrap()
+        );
+        assert_eq!(String::from_utf8(buf).unwrap(), expected);
+
+        let mut reader = Reader::from_reader(data.as_bytes());
+        let headers = reader.headers().unwrap().clone();
+        let rules = vec![MaskRule {
+            column: "missing".to_string(),
+            mode: MaskMode::Hash,
+        }];
+        let mut buf = Vec::new();
+        assert!(mask_csv(
+            reader,
+            &headers,
+            &masker,
+            &rules,
+            Box::new(&mut buf),
+            OutputFormat::Csv
+        )
+        .is_err());
+    }
+}
@@ -0,0 +1,540 @@
+use super::csv_reader::open_csv;
+use super::data_to_output;
+use super::record::create_record_writer;
+use crate::cli::{CsvReadOpts, OutputFormat};
+use anyhow::{anyhow, bail, Result};
+use csv::{Reader, StringRecord};
+use serde_json::{Map, Value};
+use std::cmp::Ordering;
+use std::io::{Read, Write};
+use std::iter::Peekable;
+use std::str::Chars;
+
+pub fn process_csv_query(
+    input: &str,
+    read: &CsvReadOpts,
+    query: &str,
+    output: &str,
+    format: OutputFormat,
+) -> Result<()> {
+    let (reader, headers) = open_csv(input, read)?;
+    let query = Query::parse(query, &headers)?;
+    let writer = data_to_output(output)?;
+    run_query(reader, &headers, &query, writer, format)
+}
+
+fn run_query<'a>(
+    mut reader: Reader<impl Read>,
+    headers: &StringRecord,
+    query: &Query,
+    writer: Box<dyn Write + 'a>,
+    format: OutputFormat,
+) -> Result<()> {
+    let mut writer = create_record_writer(format, writer)?;
+    let limit = query.limit.unwrap_or(usize::MAX);
+    let mut record = StringRecord::new();
+
+    if query.order_by.is_empty() {
+        // 无排序时流式输出，达到 limit 后立即停止读取
+        let mut count = 0;
+        while count < limit && reader.read_record(&mut record)? {
+            if query.matches(&record) {
+                writer.write_record(&query.project(headers, &record))?;
+                count += 1;
+            }
+        }
+    } else {
+        let mut rows = Vec::new();
+        while reader.read_record(&mut record)? {
+            if query.matches(&record) {
+                rows.push(record.clone());
+            }
+        }
+        rows.sort_by(|a, b| query.compare(a, b));
+        for row in rows.iter().take(limit) {
+            writer.write_record(&query.project(headers, row))?;
+        }
+    }
+    writer.finish()
+}
+
+#[derive(Debug, Default)]
+pub struct Query {
+    select: Option<Vec<usize>>,
+    filter: Option<Expr>,
+    order_by: Vec<(usize, bool)>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug)]
+enum Expr {
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    Not(Box<Expr>),
+    Compare(Operand, CompareOp, Operand),
+    Like(Operand, String),
+    IsNull(Operand),
+}
+
+#[derive(Debug)]
+enum Operand {
+    Column(usize),
+    Literal(String),
+}
+
+#[derive(Debug, Clone, Copy)]
+enum CompareOp {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Quoted(String),
+    Str(String),
+    Num(String),
+    Op(String),
+    Comma,
+    Star,
+    LParen,
+    RParen,
+}
+
+impl Query {
+    pub fn parse(query: &str, headers: &StringRecord) -> Result<Self> {
+        let tokens = tokenize(query)?;
+        let mut parser = Parser {
+            tokens,
+            pos: 0,
+            headers,
+        };
+        let query = parser.query()?;
+        if let Some(token) = parser.peek() {
+            bail!("Unexpected token in query: {:?}", token);
+        }
+        Ok(query)
+    }
+
+    fn matches(&self, record: &StringRecord) -> bool {
+        self.filter.as_ref().is_none_or(|expr| expr.eval(record))
+    }
+
+    fn project(&self, headers: &StringRecord, record: &StringRecord) -> Value {
+        let cell = |i: usize| {
+            let name = headers.get(i).unwrap_or_default().to_string();
+            let value = record.get(i).unwrap_or_default().to_string();
+            (name, Value::String(value))
+        };
+        let map: Map<String, Value> = match &self.select {
+            Some(columns) => columns.iter().map(|i| cell(*i)).collect(),
+            None => (0..headers.len()).map(cell).collect(),
+        };
+        Value::Object(map)
+    }
+
+    fn compare(&self, a: &StringRecord, b: &StringRecord) -> Ordering {
+        self.order_by
+            .iter()
+            .map(|(i, desc)| {
+                let ord = compare_cells(a.get(*i).unwrap_or(""), b.get(*i).unwrap_or(""));
+                if *desc {
+                    ord.reverse()
+                } else {
+                    ord
+                }
+            })
+            .find(|ord| ord.is_ne())
+            .unwrap_or(Ordering::Equal)
+    }
+}
+
+impl Expr {
+    fn eval(&self, record: &StringRecord) -> bool {
+        match self {
+            Expr::And(a, b) => a.eval(record) && b.eval(record),
+            Expr::Or(a, b) => a.eval(record) || b.eval(record),
+            Expr::Not(e) => !e.eval(record),
+            Expr::Compare(a, op, b) => {
+                let ord = compare_cells(a.value(record), b.value(record));
+                match op {
+                    CompareOp::Eq => ord.is_eq(),
+                    CompareOp::Ne => ord.is_ne(),
+                    CompareOp::Lt => ord.is_lt(),
+                    CompareOp::Le => ord.is_le(),
+                    CompareOp::Gt => ord.is_gt(),
+                    CompareOp::Ge => ord.is_ge(),
+                }
+            }
+            Expr::Like(a, pattern) => like(a.value(record), pattern),
+            Expr::IsNull(a) => a.value(record).trim().is_empty(),
+        }
+    }
+}
+
+impl Operand {
+    fn value<'a>(&'a self, record: &'a StringRecord) -> &'a str {
+        match self {
+            Operand::Column(i) => record.get(*i).unwrap_or(""),
+            Operand::Literal(s) => s,
+        }
+    }
+}
+
+// 两边都能解析为数字时按数值比较，否则按字符串比较
+pub(crate) fn compare_cells(a: &str, b: &str) -> Ordering {
+    match (a.trim().parse::<f64>(), b.trim().parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.partial_cmp(&b).unwrap_or(Ordering::Equal),
+        _ => a.cmp(b),
+    }
+}
+
+// sql like：% 匹配任意长度，_ 匹配单个字符
+fn like(value: &str, pattern: &str) -> bool {
+    let value = value.chars().collect::<Vec<_>>();
+    let pattern = pattern.chars().collect::<Vec<_>>();
+    let (mut v, mut p) = (0, 0);
+    let mut backtrack = None;
+    while v < value.len() {
+        if p < pattern.len() && (pattern[p] == '_' || pattern[p] == value[v]) {
+            v += 1;
+            p += 1;
+        } else if p < pattern.len() && pattern[p] == '%' {
+            backtrack = Some((p, v));
+            p += 1;
+        } else if let Some((bp, bv)) = backtrack {
+            p = bp + 1;
+            v = bv + 1;
+            backtrack = Some((bp, bv + 1));
+        } else {
+            return false;
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '%')
+}
+
+fn tokenize(query: &str) -> Result<Vec<Token>> {
+    let mut tokens = Vec::new();
+    let mut chars = query.chars().peekable();
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            ',' => {
+                chars.next();
+                tokens.push(Token::Comma);
+            }
+            '*' => {
+                chars.next();
+                tokens.push(Token::Star);
+            }
+            '(' => {
+                chars.next();
+                tokens.push(Token::LParen);
+            }
+            ')' => {
+                chars.next();
+                tokens.push(Token::RParen);
+            }
+            '\'' => tokens.push(Token::Str(read_quoted(&mut chars, '\'')?)),
+            '"' => tokens.push(Token::Quoted(read_quoted(&mut chars, '"')?)),
+            '`' => tokens.push(Token::Quoted(read_quoted(&mut chars, '`')?)),
+            '=' | '!' | '<' | '>' => {
+                let mut op = String::from(c);
+                chars.next();
+                if let Some(&next) = chars.peek() {
+                    if next == '=' || (c == '<' && next == '>') {
+                        op.push(next);
+                        chars.next();
+                    }
+                }
+                if op == "!" {
+                    bail!("Invalid operator '!' in query");
+                }
+                tokens.push(Token::Op(op));
+            }
+            c if c.is_ascii_digit() || c == '-' || c == '.' => {
+                let mut num = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_ascii_alphanumeric() || c == '.' || c == '-' || c == '+' {
+                        num.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(Token::Num(num));
+            }
+            c if c.is_alphanumeric() || c == '_' => {
+                let mut ident = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_alphanumeric() || c == '_' || c == '.' {
+                        ident.push(c);
+                        chars.next();
+                    } else {
+                        break;
+                    }
+                }
+                tokens.push(Token::Ident(ident));
+            }
+            c => bail!("Unexpected character {:?} in query", c),
+        }
+    }
+    Ok(tokens)
+}
+
+fn read_quoted(chars: &mut Peekable<Chars>, quote: char) -> Result<String> {
+    chars.next();
+    let mut s = String::new();
+    while let Some(c) = chars.next() {
+        if c == quote {
+            // 连续两个引号表示转义
+            if chars.peek() == Some(&quote) {
+                chars.next();
+            } else {
+                return Ok(s);
+            }
+        }
+        s.push(c);
+    }
+    bail!("Unterminated {} in query", quote)
+}
+
+struct Parser<'a> {
+    tokens: Vec<Token>,
+    pos: usize,
+    headers: &'a StringRecord,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn keyword(&mut self, keyword: &str) -> bool {
+        match self.peek() {
+            Some(Token::Ident(ident)) if ident.eq_ignore_ascii_case(keyword) => {
+                self.pos += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn expect_keyword(&mut self, keyword: &str) -> Result<()> {
+        if self.keyword(keyword) {
+            Ok(())
+        } else {
+            bail!("Expected '{}' in query", keyword)
+        }
+    }
+
+    fn query(&mut self) -> Result<Query> {
+        let mut query = Query::default();
+        if self.keyword("select") {
+            query.select = self.select_list()?;
+        }
+        if self.keyword("where") {
+            query.filter = Some(self.or_expr()?);
+        }
+        if self.keyword("order") {
+            self.expect_keyword("by")?;
+            loop {
+                let column = self.column()?;
+                let desc = if self.keyword("desc") {
+                    true
+                } else {
+                    self.keyword("asc");
+                    false
+                };
+                query.order_by.push((column, desc));
+                if self.peek() != Some(&Token::Comma) {
+                    break;
+                }
+                self.pos += 1;
+            }
+        }
+        if self.keyword("limit") {
+            query.limit = match self.next() {
+                Some(Token::Num(n)) => Some(n.parse()?),
+                v => bail!("Expected a number after limit, got {:?}", v),
+            };
+        }
+        Ok(query)
+    }
+
+    fn select_list(&mut self) -> Result<Option<Vec<usize>>> {
+        if self.peek() == Some(&Token::Star) {
+            self.pos += 1;
+            return Ok(None);
+        }
+        let mut columns = vec![self.column()?];
+        while self.peek() == Some(&Token::Comma) {
+            self.pos += 1;
+            columns.push(self.column()?);
+        }
+        Ok(Some(columns))
+    }
+
+    fn column(&mut self) -> Result<usize> {
+        match self.next() {
+            Some(Token::Ident(name)) | Some(Token::Quoted(name)) => self.resolve(&name),
+            v => bail!("Expected a column name, got {:?}", v),
+        }
+    }
+
+    fn resolve(&self, name: &str) -> Result<usize> {
+        self.headers
+            .iter()
+            .position(|h| h == name)
+            .ok_or_else(|| anyhow!("Unknown column {:?}", name))
+    }
+
+    fn or_expr(&mut self) -> Result<Expr> {
+        let mut expr = self.and_expr()?;
+        while self.keyword("or") {
+            expr = Expr::Or(Box::new(expr), Box::new(self.and_expr()?));
+        }
+        Ok(expr)
+    }
+
+    fn and_expr(&mut self) -> Result<Expr> {
+        let mut expr = self.not_expr()?;
+        while self.keyword("and") {
+            expr = Expr::And(Box::new(expr), Box::new(self.not_expr()?));
+        }
+        Ok(expr)
+    }
+
+    fn not_expr(&mut self) -> Result<Expr> {
+        if self.keyword("not") {
+            return Ok(Expr::Not(Box::new(self.not_expr()?)));
+        }
+        if self.peek() == Some(&Token::LParen) {
+            self.pos += 1;
+            let expr = self.or_expr()?;
+            match self.next() {
+                Some(Token::RParen) => return Ok(expr),
+                v => bail!("Expected ')' in query, got {:?}", v),
+            }
+        }
+        self.predicate()
+    }
+
+    fn predicate(&mut self) -> Result<Expr> {
+        let left = self.operand()?;
+        if self.keyword("is") {
+            let negated = self.keyword("not");
+            self.expect_keyword("null")?;
+            let expr = Expr::IsNull(left);
+            return Ok(if negated {
+                Expr::Not(Box::new(expr))
+            } else {
+                expr
+            });
+        }
+        let negated = self.keyword("not");
+        if self.keyword("like") {
+            let pattern = match self.next() {
+                Some(Token::Str(s)) => s,
+                v => bail!("Expected a string pattern after like, got {:?}", v),
+            };
+            let expr = Expr::Like(left, pattern);
+            return Ok(if negated {
+                Expr::Not(Box::new(expr))
+            } else {
+                expr
+            });
+        }
+        if negated {
+            bail!("Expected 'like' after 'not' in query");
+        }
+        let op = match self.next() {
+            Some(Token::Op(op)) => match op.as_str() {
+                "=" | "==" => CompareOp::Eq,
+                "!=" | "<>" => CompareOp::Ne,
+                "<" => CompareOp::Lt,
+                "<=" => CompareOp::Le,
+                ">" => CompareOp::Gt,
+                ">=" => CompareOp::Ge,
+                v => bail!("Unsupported operator {:?}", v),
+            },
+            v => bail!("Expected a comparison operator, got {:?}", v),
+        };
+        let right = self.operand()?;
+        Ok(Expr::Compare(left, op, right))
+    }
+
+    fn operand(&mut self) -> Result<Operand> {
+        match self.next() {
+            Some(Token::Ident(name)) | Some(Token::Quoted(name)) => {
+                Ok(Operand::Column(self.resolve(&name)?))
+            }
+            Some(Token::Str(s)) | Some(Token::Num(s)) => Ok(Operand::Literal(s)),
+            v => bail!("Expected a column or literal, got {:?}", v),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn query_juventus(query: &str, format: OutputFormat) -> String {
+        let mut reader = Reader::from_path("assets/juventus.csv").unwrap();
+        let headers = reader.headers().unwrap().clone();
+        let query = Query::parse(query, &headers).unwrap();
+        let mut buf = Vec::new();
+        run_query(reader, &headers, &query, Box::new(&mut buf), format).unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn test_query_select_where_order_limit() {
+        let result = query_juventus(
+            r#"select Name, "Kit Number" where Nationality = 'Italy' and "Kit Number" > 10 order by "Kit Number" desc limit 2"#,
+            OutputFormat::Csv,
+        );
+        assert_eq!(
+            result,
+            "Name,Kit Number\nGianluigi Buffon,77\nMattia Perin,37\n"
+        );
+    }
+
+    #[test]
+    fn test_query_like_and_null() {
+        let result = query_juventus(
+            "select Name where Name like '%Perin' or (Position is null)",
+            OutputFormat::Ndjson,
+        );
+        assert_eq!(result, "{\"Name\":\"Mattia Perin\"}\n");
+    }
+
+    #[test]
+    fn test_query_errors() {
+        let headers = StringRecord::from(vec!["a", "b"]);
+        assert!(Query::parse("select c", &headers).is_err());
+        assert!(Query::parse("where a >", &headers).is_err());
+        assert!(Query::parse("select a limit x", &headers).is_err());
+        assert!(Query::parse("where a = 'x", &headers).is_err());
+    }
+
+    #[test]
+    fn test_like() {
+        assert!(like("hello", "h%o"));
+        assert!(like("hello", "h_llo"));
+        assert!(like("hello", "%"));
+        assert!(!like("hello", "h%x"));
+    }
+}
@@ -0,0 +1,332 @@
+use super::data_from_input;
+use super::record::infer::ColumnType;
+use super::spreadsheet::{is_spreadsheet, read_sheet};
+use crate::cli::CsvReadOpts;
+use anyhow::Result;
+use chardetng::EncodingDetector;
+use csv::{Reader, ReaderBuilder, StringRecord};
+use encoding_rs::{Encoding, UTF_8};
+use encoding_rs_io::DecodeReaderBytesBuilder;
+use std::io::{Cursor, Read};
+
+const SNIFF_BYTES: u64 = 64 * 1024;
+const SNIFF_DELIMITERS: [u8; 5] = [b',', b'\t', b';', b'|', b':'];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Dialect {
+    pub delimiter: u8,
+    pub quote: u8,
+    pub header: bool,
+}
+
+pub fn open_csv(input: &str, opts: &CsvReadOpts) -> Result<(Reader<Box<dyn Read>>, StringRecord)> {
+    if is_spreadsheet(input) {
+        // 电子表格先转成 csv，之后与普通 csv 走同一套流程，只保留表头设置
+        let data = read_sheet(input, opts.sheet.as_deref())?;
+        let opts = CsvReadOpts {
+            delimiter: b',',
+            sniff: false,
+            encoding: Some(UTF_8),
+            ..opts.clone()
+        };
+        return csv_from_reader(Box::new(Cursor::new(data)), &opts);
+    }
+    csv_from_reader(data_from_input(input)?, opts)
+}
+
+// 所有 csv 子命令共用的 reader 构建逻辑：编码转换、分隔符、表头以及自动探测
+pub fn csv_from_reader<'a>(
+    reader: Box<dyn Read + 'a>,
+    opts: &CsvReadOpts,
+) -> Result<(Reader<Box<dyn Read + 'a>>, StringRecord)> {
+    let reader = decode(reader, opts.encoding)?;
+    let (reader, dialect) = if opts.sniff {
+        sniff(reader, opts)?
+    } else {
+        (reader, resolve_dialect(&[], opts))
+    };
+
+    let mut reader = ReaderBuilder::new()
+        .delimiter(dialect.delimiter)
+        .quote(dialect.quote)
+        .has_headers(dialect.header)
+        .from_reader(reader);
+    let headers = if dialect.header {
+        let mut headers = reader.headers()?.clone();
+        if headers.get(0).is_some_and(|h| h.starts_with('\u{feff}')) {
+            headers = headers
+                .iter()
+                .enumerate()
+                .map(|(i, h)| {
+                    if i == 0 {
+                        h.trim_start_matches('\u{feff}')
+                    } else {
+                        h
+                    }
+                })
+                .collect();
+            reader.set_headers(headers.clone());
+        }
+        headers
+    } else {
+        // 无表头时按第一行的列数生成 col_1..col_n
+        let len = reader.headers()?.len();
+        (1..=len).map(|i| format!("col_{}", i)).collect()
+    };
+    Ok((reader, headers))
+}
+
+// 读取转码后的完整文本，不经过 csv 解析，用于检查文件本身的结构问题
+pub fn read_csv_text(input: &str, opts: &CsvReadOpts) -> Result<(String, Dialect)> {
+    let mut text = String::new();
+    decode(data_from_input(input)?, opts.encoding)?.read_to_string(&mut text)?;
+    let dialect = if opts.sniff {
+        let end = text.floor_char_boundary(SNIFF_BYTES as usize);
+        Dialect::sniff(&text.as_bytes()[..end])
+    } else {
+        Dialect {
+            delimiter: opts.delimiter,
+            quote: b'"',
+            header: opts.header,
+        }
+    };
+    Ok((text, dialect))
+}
+
+// 统一转码为 utf-8：有 BOM 时以 BOM 为准并去掉 BOM；未指定编码时根据内容猜测
+fn decode<'a>(
+    mut reader: Box<dyn Read + 'a>,
+    encoding: Option<&'static Encoding>,
+) -> Result<Box<dyn Read + 'a>> {
+    let encoding = match encoding {
+        Some(encoding) => encoding,
+        None => {
+            let mut buf = Vec::new();
+            reader.by_ref().take(SNIFF_BYTES).read_to_end(&mut buf)?;
+            let encoding = detect_encoding(&buf);
+            reader = Box::new(Cursor::new(buf).chain(reader));
+            encoding
+        }
+    };
+    let reader = DecodeReaderBytesBuilder::new()
+        .encoding((encoding != UTF_8).then_some(encoding))
+        .bom_override(true)
+        .strip_bom(true)
+        .build(reader);
+    Ok(Box::new(reader))
+}
+
+pub fn detect_encoding(sample: &[u8]) -> &'static Encoding {
+    if let Some((encoding, _)) = Encoding::for_bom(sample) {
+        return encoding;
+    }
+    match std::str::from_utf8(sample) {
+        Ok(_) => UTF_8,
+        // 只是截断在多字节字符中间，仍然是 utf-8
+        Err(e) if e.error_len().is_none() => UTF_8,
+        Err(_) => {
+            let mut detector = EncodingDetector::new();
+            detector.feed(sample, false);
+            detector.guess(None, true)
+        }
+    }
+}
+
+// 按读取选项确定方言，--sniff 时从文件开头的样本中探测
+pub fn resolve_dialect(sample: &[u8], opts: &CsvReadOpts) -> Dialect {
+    if !opts.sniff {
+        return Dialect {
+            delimiter: opts.delimiter,
+            quote: b'"',
+            header: opts.header,
+        };
+    }
+    let sample = if sample.len() as u64 >= SNIFF_BYTES {
+        // 丢弃最后一行不完整的数据
+        let end = sample
+            .iter()
+            .rposition(|b| *b == b'\n')
+            .unwrap_or(sample.len());
+        &sample[..end]
+    } else {
+        sample
+    };
+    Dialect::sniff(sample)
+}
+
+fn sniff<'a>(
+    mut reader: Box<dyn Read + 'a>,
+    opts: &CsvReadOpts,
+) -> Result<(Box<dyn Read + 'a>, Dialect)> {
+    let mut buf = Vec::new();
+    reader.by_ref().take(SNIFF_BYTES).read_to_end(&mut buf)?;
+    let dialect = resolve_dialect(&buf, opts);
+    let reader: Box<dyn Read + 'a> = Box::new(Cursor::new(buf).chain(reader));
+    Ok((reader, dialect))
+}
+
+impl Dialect {
+    pub fn sniff(sample: &[u8]) -> Self {
+        let quote = sniff_quote(sample);
+        let delimiter = SNIFF_DELIMITERS
+            .iter()
+            .copied()
+            .max_by_key(|delimiter| delimiter_score(sample, *delimiter, quote))
+            .unwrap_or(b',');
+        let rows = parse_rows(sample, delimiter, quote);
+        Dialect {
+            delimiter,
+            quote,
+            header: sniff_header(&rows),
+        }
+    }
+}
+
+fn parse_rows(sample: &[u8], delimiter: u8, quote: u8) -> Vec<StringRecord> {
+    ReaderBuilder::new()
+        .delimiter(delimiter)
+        .quote(quote)
+        .has_headers(false)
+        .flexible(true)
+        .from_reader(sample)
+        .records()
+        .map_while(Result::ok)
+        .collect()
+}
+
+// 分数：列数一致的行数越多越好，其次列数越多越好
+fn delimiter_score(sample: &[u8], delimiter: u8, quote: u8) -> (usize, usize) {
+    let rows = parse_rows(sample, delimiter, quote);
+    let Some(first) = rows.first() else {
+        return (0, 0);
+    };
+    let fields = first.len();
+    if fields < 2 {
+        return (0, 0);
+    }
+    let consistent = rows.iter().filter(|row| row.len() == fields).count();
+    (consistent, fields)
+}
+
+fn sniff_quote(sample: &[u8]) -> u8 {
+    let count = |quote: u8| {
+        sample
+            .windows(2)
+            .filter(|w| {
+                w[1] == quote && (SNIFF_DELIMITERS.contains(&w[0]) || w[0] == b'\n')
+                    || w[0] == quote && (SNIFF_DELIMITERS.contains(&w[1]) || w[1] == b'\n')
+            })
+            .count()
+    };
+    if count(b'\'') > count(b'"') {
+        b'\''
+    } else {
+        b'"'
+    }
+}
+
+// 比较第一行与其余行的类型：其余行为数值等类型而第一行为字符串，则第一行是表头
+fn sniff_header(rows: &[StringRecord]) -> bool {
+    let Some((first, rest)) = rows.split_first() else {
+        return true;
+    };
+    let mut votes = 0i32;
+    for (i, cell) in first.iter().enumerate() {
+        let column = rest
+            .iter()
+            .filter_map(|row| row.get(i))
+            .map(ColumnType::detect)
+            .fold(ColumnType::Null, ColumnType::merge);
+        if matches!(column, ColumnType::String | ColumnType::Null) {
+            continue;
+        }
+        if ColumnType::detect(cell) == column {
+            votes -= 1;
+        } else {
+            votes += 1;
+        }
+    }
+    if votes != 0 {
+        return votes > 0;
+    }
+    let mut names = first.iter().collect::<Vec<_>>();
+    let len = names.len();
+    names.sort_unstable();
+    names.dedup();
+    names.len() == len && first.iter().all(|name| !name.trim().is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_dialect_sniff() {
+        let dialect = Dialect::sniff(b"name;age;city\nalice;30;Turin\nbob;25;\"Milan; IT\"\n");
+        assert_eq!(
+            dialect,
+            Dialect {
+                delimiter: b';',
+                quote: b'"',
+                header: true
+            }
+        );
+
+        let dialect = Dialect::sniff(b"alice\t30\tTurin\nbob\t25\tMilan\n");
+        assert_eq!(dialect.delimiter, b'\t');
+        assert!(!dialect.header);
+    }
+
+    #[test]
+    fn test_csv_from_reader_encoding() {
+        let opts = CsvReadOpts {
+            delimiter: b',',
+            header: true,
+            sniff: false,
+            encoding: None,
+            sheet: None,
+        };
+        let data: &[u8] = b"\xef\xbb\xbfname,city\nalice,Turin\n";
+        let (_, headers) = csv_from_reader(Box::new(data), &opts).unwrap();
+        assert_eq!(&headers[0], "name");
+
+        let (gbk, _, _) = encoding_rs::GBK.encode("姓名,城市\n张三,北京\n李四,上海\n");
+        let data = gbk.into_owned();
+        let (mut reader, headers) = csv_from_reader(Box::new(&data[..]), &opts).unwrap();
+        assert_eq!(headers, StringRecord::from(vec!["姓名", "城市"]));
+        let record = reader.records().next().unwrap().unwrap();
+        assert_eq!(&record[1], "北京");
+
+        let mut utf16 = vec![0xff, 0xfe];
+        for unit in "a,b\n1,2\n".encode_utf16() {
+            utf16.extend_from_slice(&unit.to_le_bytes());
+        }
+        let (_, headers) = csv_from_reader(Box::new(&utf16[..]), &opts).unwrap();
+        assert_eq!(headers, StringRecord::from(vec!["a", "b"]));
+
+        let opts = CsvReadOpts {
+            encoding: Encoding::for_label(b"latin1"),
+            ..opts
+        };
+        let data: &[u8] = b"name\nJos\xe9\n";
+        let (mut reader, _) = csv_from_reader(Box::new(data), &opts).unwrap();
+        assert_eq!(&reader.records().next().unwrap().unwrap()[0], "José");
+    }
+
+    #[test]
+    fn test_csv_from_reader_headerless() {
+        let opts = CsvReadOpts {
+            delimiter: b'|',
+            header: false,
+            sniff: false,
+            encoding: None,
+            sheet: None,
+        };
+        let data: &[u8] = b"a|1\nb|2\n";
+        let (mut reader, headers) = csv_from_reader(Box::new(data), &opts).unwrap();
+        assert_eq!(headers, StringRecord::from(vec!["col_1", "col_2"]));
+        let records = reader.records().collect::<Result<Vec<_>, _>>().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(&records[0][0], "a");
+    }
+}
@@ -0,0 +1,119 @@
+use super::record::read_records;
+use super::{data_from_input, data_to_output};
+use crate::cli::OutputFormat;
+use anyhow::Result;
+use serde_json::{Map, Value};
+use std::collections::HashSet;
+use std::io::{Read, Write};
+
+pub fn process_csv_reverse(
+    input: &str,
+    output: &str,
+    format: OutputFormat,
+    separator: &str,
+    toml_root: Option<&str>,
+) -> Result<()> {
+    let reader = data_from_input(input)?;
+    let writer = data_to_output(output)?;
+    records_to_csv(reader, writer, format, separator, toml_root)
+}
+
+fn records_to_csv(
+    reader: impl Read,
+    writer: impl Write,
+    format: OutputFormat,
+    separator: &str,
+    root: Option<&str>,
+) -> Result<()> {
+    let records = read_records(format, reader, root)?
+        .into_iter()
+        .map(|record| flatten_record(record, separator))
+        .collect::<Vec<_>>();
+
+    // 所有记录字段的并集，按首次出现的顺序排列，保证表头稳定
+    let mut headers = Vec::new();
+    let mut seen = HashSet::new();
+    for record in &records {
+        for key in record.keys() {
+            if seen.insert(key.as_str()) {
+                headers.push(key.as_str());
+            }
+        }
+    }
+
+    let mut writer = csv::Writer::from_writer(writer);
+    writer.write_record(&headers)?;
+    for record in &records {
+        let row = headers
+            .iter()
+            .map(|key| record.get(*key).and_then(Value::as_str).unwrap_or_default());
+        writer.write_record(row)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+fn flatten_record(record: Value, separator: &str) -> Map<String, Value> {
+    let mut row = Map::new();
+    match record {
+        Value::Object(map) => {
+            for (key, value) in map {
+                flatten_value(&key, value, separator, &mut row);
+            }
+        }
+        v => {
+            row.insert("value".to_string(), cell_text(v, separator).into());
+        }
+    }
+    row
+}
+
+// 嵌套对象展开为 a.b.c 形式的列名
+fn flatten_value(prefix: &str, value: Value, separator: &str, row: &mut Map<String, Value>) {
+    match value {
+        Value::Object(map) if !map.is_empty() => {
+            for (key, value) in map {
+                flatten_value(&format!("{}.{}", prefix, key), value, separator, row);
+            }
+        }
+        v => {
+            row.insert(prefix.to_string(), cell_text(v, separator).into());
+        }
+    }
+}
+
+fn cell_text(value: Value, separator: &str) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s,
+        Value::Array(items) => items
+            .into_iter()
+            .map(|item| match item {
+                Value::Object(_) | Value::Array(_) => item.to_string(),
+                v => cell_text(v, separator),
+            })
+            .collect::<Vec<_>>()
+            .join(separator),
+        Value::Object(map) if map.is_empty() => String::new(),
+        v => v.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_records_to_csv() {
+        let input = r#"[
+            {"name": "a", "tags": ["x", "y"], "address": {"city": "Turin", "zip": 10121}},
+            {"name": "b", "active": true}
+        ]"#;
+        let mut buf = Vec::new();
+        records_to_csv(input.as_bytes(), &mut buf, OutputFormat::Json, "|", None).unwrap();
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "name,tags,address.city,address.zip,active\na,x|y,Turin,10121,\nb,,,,true\n"
+        );
+    }
+}
@@ -0,0 +1,93 @@
+use super::csv_reader::open_csv;
+use super::record::open_record_writer;
+use crate::cli::{CsvReadOpts, CsvWriteOpts, OutputFormat};
+use anyhow::Result;
+use csv::{Reader, StringRecord};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde_json::{Map, Value};
+use std::io::Read;
+
+pub fn process_csv_sample(
+    input: &str,
+    read: &CsvReadOpts,
+    size: usize,
+    seed: Option<u64>,
+    output: &str,
+    format: OutputFormat,
+) -> Result<()> {
+    let (reader, headers) = open_csv(input, read)?;
+    let mut rng = match seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let rows = reservoir_sample(reader, size, &mut rng)?;
+    let mut writer = open_record_writer(format, output, &CsvWriteOpts::default())?;
+    writer.set_headers(&headers.iter().map(String::from).collect::<Vec<_>>());
+    for record in rows {
+        let map = headers
+            .iter()
+            .zip(record.iter())
+            .map(|(name, value)| (name.to_string(), Value::String(value.to_string())))
+            .collect::<Map<_, _>>();
+        writer.write_record(&Value::Object(map))?;
+    }
+    writer.finish()?;
+    Ok(())
+}
+
+// 蓄水池抽样：只遍历一次，内存中最多保留 size 行；输出保持原文件中的顺序
+fn reservoir_sample(
+    mut reader: Reader<impl Read>,
+    size: usize,
+    rng: &mut impl Rng,
+) -> Result<Vec<StringRecord>> {
+    // size 来自用户输入，不预先分配，避免文件很小时也按 size 申请内存
+    let mut reservoir: Vec<(usize, StringRecord)> = Vec::new();
+    let mut record = StringRecord::new();
+    let mut seen = 0;
+    while reader.read_record(&mut record)? {
+        if reservoir.len() < size {
+            reservoir.push((seen, record.clone()));
+        } else {
+            let j = rng.gen_range(0..=seen);
+            if j < size {
+                reservoir[j] = (seen, record.clone());
+            }
+        }
+        seen += 1;
+    }
+    reservoir.sort_by_key(|(i, _)| *i);
+    Ok(reservoir.into_iter().map(|(_, record)| record).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(size: usize, seed: u64) -> Vec<String> {
+        let data = (1..=100).fold("n\n".to_string(), |acc, i| acc + &format!("{}\n", i));
+        let reader = Reader::from_reader(data.as_bytes());
+        let mut rng = StdRng::seed_from_u64(seed);
+        reservoir_sample(reader, size, &mut rng)
+            .unwrap()
+            .into_iter()
+            .map(|r| r[0].to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_reservoir_sample() {
+        let rows = sample(10, 7);
+        assert_eq!(rows.len(), 10);
+        assert_eq!(rows, sample(10, 7));
+        assert_ne!(rows, sample(10, 8));
+        let numbers = rows
+            .iter()
+            .map(|r| r.parse::<u32>().unwrap())
+            .collect::<Vec<_>>();
+        assert!(numbers.windows(2).all(|w| w[0] < w[1]));
+        assert_eq!(sample(200, 7).len(), 100);
+        assert!(sample(0, 7).is_empty());
+    }
+}
@@ -0,0 +1,161 @@
+use super::csv_reader::open_csv;
+use super::data_to_output;
+use super::record::infer::{is_timestamp, ColumnType};
+use super::sql::{create_table, default_table_name, SqlColumn};
+use crate::cli::{CsvReadOpts, SchemaFormat};
+use anyhow::Result;
+use csv::{Reader, StringRecord};
+use serde_json::{json, Map, Value};
+use std::io::Read;
+
+pub fn process_csv_schema(
+    input: &str,
+    read: &CsvReadOpts,
+    format: SchemaFormat,
+    table: Option<&str>,
+    output: &str,
+) -> Result<()> {
+    let (reader, headers) = open_csv(input, read)?;
+    let columns = profile_columns(reader, &headers)?;
+    let table = table
+        .map(String::from)
+        .unwrap_or_else(|| default_table_name(input));
+    let text = match format {
+        SchemaFormat::JsonSchema => {
+            serde_json::to_string_pretty(&json_schema(&table, &columns))? + "\n"
+        }
+        SchemaFormat::Sql(dialect) => create_table(dialect, &table, &columns, false),
+    };
+    let mut writer = data_to_output(output)?;
+    writer.write_all(text.as_bytes())?;
+    writer.flush()?;
+    Ok(())
+}
+
+// 扫描整个文件推断每列的类型和是否有空值
+pub fn profile_columns(
+    mut reader: Reader<impl Read>,
+    headers: &StringRecord,
+) -> Result<Vec<SqlColumn>> {
+    let mut columns = headers
+        .iter()
+        .map(|name| SqlColumn {
+            name: name.to_string(),
+            column_type: ColumnType::Null,
+            nullable: false,
+            timestamp: false,
+        })
+        .collect::<Vec<_>>();
+    let mut rows = 0;
+    let mut record = StringRecord::new();
+    while reader.read_record(&mut record)? {
+        rows += 1;
+        for (i, column) in columns.iter_mut().enumerate() {
+            let cell = record.get(i).unwrap_or_default();
+            let cell_type = ColumnType::detect(cell);
+            match cell_type {
+                ColumnType::Null => column.nullable = true,
+                ColumnType::Date if is_timestamp(cell) => column.timestamp = true,
+                _ => {}
+            }
+            column.column_type = column.column_type.merge(cell_type);
+        }
+    }
+    if rows == 0 {
+        columns.iter_mut().for_each(|c| c.nullable = true);
+    }
+    Ok(columns)
+}
+
+// 描述 `rcli csv --infer -f json` 输出的 JSON 数组，空单元格在非字符串列中为 null
+fn json_schema(table: &str, columns: &[SqlColumn]) -> Value {
+    let properties = columns
+        .iter()
+        .map(|column| (column.name.clone(), column_schema(column)))
+        .collect::<Map<_, _>>();
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": table,
+        "type": "array",
+        "items": {
+            "type": "object",
+            "properties": properties,
+            "required": columns.iter().map(|c| c.name.as_str()).collect::<Vec<_>>(),
+        },
+    })
+}
+
+fn column_schema(column: &SqlColumn) -> Value {
+    let json_type = match column.column_type {
+        ColumnType::Null => "null",
+        ColumnType::Bool => "boolean",
+        ColumnType::Integer => "integer",
+        ColumnType::Float => "number",
+        ColumnType::Date | ColumnType::String => "string",
+    };
+    let mut schema = Map::new();
+    let nullable =
+        column.nullable && !matches!(column.column_type, ColumnType::Null | ColumnType::String);
+    schema.insert(
+        "type".to_string(),
+        if nullable {
+            json!([json_type, "null"])
+        } else {
+            json!(json_type)
+        },
+    );
+    if column.column_type == ColumnType::Date {
+        let format = if column.timestamp {
+            "date-time"
+        } else {
+            "date"
+        };
+        schema.insert("format".to_string(), json!(format));
+    }
+    Value::Object(schema)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cli::SqlDialect;
+
+    fn columns() -> Vec<SqlColumn> {
+        let data = "id,name,score,joined,active,note\n\
+                    1,alice,9.5,2024-01-02,true,\n\
+                    2,bob,,2024-01-03T10:00:00Z,false,\n";
+        let mut reader = Reader::from_reader(data.as_bytes());
+        let headers = reader.headers().unwrap().clone();
+        profile_columns(reader, &headers).unwrap()
+    }
+
+    #[test]
+    fn test_json_schema() {
+        let schema = json_schema("people", &columns());
+        assert_eq!(schema["title"], "people");
+        let properties = &schema["items"]["properties"];
+        assert_eq!(properties["id"], json!({"type": "integer"}));
+        assert_eq!(properties["name"], json!({"type": "string"}));
+        assert_eq!(properties["score"], json!({"type": ["number", "null"]}));
+        assert_eq!(
+            properties["joined"],
+            json!({"type": "string", "format": "date-time"})
+        );
+        assert_eq!(properties["active"], json!({"type": "boolean"}));
+        assert_eq!(properties["note"], json!({"type": "null"}));
+        assert_eq!(schema["items"]["required"].as_array().unwrap().len(), 6);
+    }
+
+    #[test]
+    fn test_create_table() {
+        let columns = columns();
+        assert_eq!(
+            create_table(SqlDialect::Postgres, "people", &columns, false),
+            "CREATE TABLE \"people\" (\n    \"id\" BIGINT NOT NULL,\n    \"name\" TEXT NOT NULL,\n    \
+             \"score\" DOUBLE PRECISION,\n    \"joined\" TIMESTAMPTZ NOT NULL,\n    \
+             \"active\" BOOLEAN NOT NULL,\n    \"note\" TEXT\n);\n"
+        );
+        assert!(create_table(SqlDialect::Sqlite, "people", &columns, true)
+            .contains("EXISTS \"people\" (\n    \"id\" INTEGER NOT NULL,\n    \"name\" TEXT NOT NULL,\n    \"score\" REAL,\n    \"joined\" TEXT NOT NULL,\n    \"active\" INTEGER"));
+    }
+}
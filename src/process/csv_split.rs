@@ -0,0 +1,149 @@
+use super::csv_reader::open_csv;
+use super::data_to_output;
+use super::sql::default_table_name;
+use crate::cli::{CsvReadOpts, SplitBy};
+use anyhow::Result;
+use csv::{Reader, StringRecord, Writer};
+use std::io::{Read, Write};
+use std::path::Path;
+
+struct Chunk<'a> {
+    writer: Writer<Box<dyn Write + 'a>>,
+    rows: u64,
+    bytes: u64,
+}
+
+pub fn process_csv_split(
+    input: &str,
+    read: &CsvReadOpts,
+    by: SplitBy,
+    dir: &Path,
+    prefix: Option<&str>,
+) -> Result<()> {
+    let (reader, headers) = open_csv(input, read)?;
+    let prefix = prefix.map_or_else(|| default_table_name(input), String::from);
+    let chunks = split_csv(reader, &headers, by, |n| {
+        let path = dir.join(format!("{}_{:04}.csv", prefix, n));
+        data_to_output(&path.to_string_lossy())
+    })?;
+    println!("Split into {} file(s) in {}", chunks, dir.display());
+    Ok(())
+}
+
+// 每个分片都重复表头；按字节切分时单行超过上限也会单独成为一个分片
+fn split_csv<'a>(
+    mut reader: Reader<impl Read>,
+    headers: &StringRecord,
+    by: SplitBy,
+    mut create: impl FnMut(usize) -> Result<Box<dyn Write + 'a>>,
+) -> Result<usize> {
+    let header_len = encoded_len(headers);
+    let mut chunk: Option<Chunk> = None;
+    let mut chunks = 0;
+    let mut record = StringRecord::new();
+    while reader.read_record(&mut record)? {
+        let len = encoded_len(&record);
+        let full = chunk.as_ref().is_none_or(|c| match by {
+            SplitBy::Rows(n) => c.rows >= n,
+            SplitBy::Bytes(n) => c.rows > 0 && c.bytes + len > n,
+        });
+        if full {
+            if let Some(mut c) = chunk.take() {
+                c.writer.flush()?;
+            }
+            chunks += 1;
+            let mut writer = Writer::from_writer(create(chunks)?);
+            writer.write_record(headers)?;
+            chunk = Some(Chunk {
+                writer,
+                rows: 0,
+                bytes: header_len,
+            });
+        }
+        if let Some(c) = chunk.as_mut() {
+            c.writer.write_record(&record)?;
+            c.rows += 1;
+            c.bytes += len;
+        }
+    }
+    if let Some(mut c) = chunk {
+        c.writer.flush()?;
+    }
+    Ok(chunks)
+}
+
+// 按 csv writer 的默认规则（必要时加引号、引号转义、\n 结尾）计算一行写出后的字节数
+fn encoded_len(record: &StringRecord) -> u64 {
+    let fields = record
+        .iter()
+        .map(|field| {
+            let quotes = field.matches('"').count();
+            if quotes > 0 || field.contains([',', '\n', '\r']) {
+                field.len() + quotes + 2
+            } else {
+                field.len()
+            }
+        })
+        .sum::<usize>();
+    (fields + record.len().max(1)) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[derive(Clone, Default)]
+    struct Shared(Rc<RefCell<Vec<u8>>>);
+
+    impl Write for Shared {
+        fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+            self.0.borrow_mut().write(buf)
+        }
+
+        fn flush(&mut self) -> std::io::Result<()> {
+            Ok(())
+        }
+    }
+
+    fn split(by: SplitBy) -> Vec<String> {
+        let data = "id,note\n1,a\n2,\"b,\"\"c\"\"\"\n3,d\n4,e\n5,f\n";
+        let mut reader = Reader::from_reader(data.as_bytes());
+        let headers = reader.headers().unwrap().clone();
+        let mut outputs = Vec::new();
+        split_csv(reader, &headers, by, |_| {
+            let out = Shared::default();
+            outputs.push(out.clone());
+            Ok(Box::new(out))
+        })
+        .unwrap();
+        outputs
+            .iter()
+            .map(|out| String::from_utf8(out.0.borrow().clone()).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_split_csv() {
+        assert_eq!(
+            split(SplitBy::Rows(2)),
+            vec![
+                "id,note\n1,a\n2,\"b,\"\"c\"\"\"\n",
+                "id,note\n3,d\n4,e\n",
+                "id,note\n5,f\n"
+            ]
+        );
+        // 表头 8 字节，之后每行 4 字节，第二行为 12 字节
+        assert_eq!(
+            split(SplitBy::Bytes(20)),
+            vec![
+                "id,note\n1,a\n",
+                "id,note\n2,\"b,\"\"c\"\"\"\n",
+                "id,note\n3,d\n4,e\n5,f\n"
+            ]
+        );
+        let record = StringRecord::from(vec!["2", "b,\"c\""]);
+        assert_eq!(encoded_len(&record), 12);
+    }
+}
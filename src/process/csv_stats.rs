@@ -0,0 +1,222 @@
+use super::csv_query::compare_cells;
+use super::csv_reader::open_csv;
+use super::data_to_output;
+use super::record::create_record_writer;
+use super::record::infer::ColumnType;
+use super::table::render_table;
+use crate::cli::{CsvReadOpts, OutputFormat};
+use anyhow::Result;
+use csv::{Reader, StringRecord};
+use serde::Serialize;
+use std::collections::HashMap;
+use std::io::Read;
+
+#[derive(Debug, Serialize)]
+pub struct ColumnStats {
+    pub name: String,
+    #[serde(rename = "type")]
+    pub column_type: ColumnType,
+    pub count: usize,
+    pub nulls: usize,
+    pub null_ratio: f64,
+    pub distinct: usize,
+    pub min: Option<String>,
+    pub max: Option<String>,
+    pub mean: Option<f64>,
+    pub stddev: Option<f64>,
+    pub top: Vec<ValueCount>,
+}
+
+#[derive(Debug, Serialize)]
+pub struct ValueCount {
+    pub value: String,
+    pub count: usize,
+}
+
+#[derive(Default)]
+struct ColumnAccumulator {
+    column_type: Option<ColumnType>,
+    count: usize,
+    nulls: usize,
+    values: HashMap<String, usize>,
+    // Welford 算法计算均值和方差，只需遍历一次
+    numeric: usize,
+    mean: f64,
+    m2: f64,
+}
+
+pub fn process_csv_stats(
+    input: &str,
+    read: &CsvReadOpts,
+    top: usize,
+    output: &str,
+    format: Option<OutputFormat>,
+) -> Result<()> {
+    let (reader, headers) = open_csv(input, read)?;
+    let stats = collect_stats(reader, &headers, top)?;
+    let mut writer = data_to_output(output)?;
+    match format {
+        Some(format) => {
+            let mut writer = create_record_writer(format, writer)?;
+            for column in &stats {
+                writer.write_record(&serde_json::to_value(column)?)?;
+            }
+            writer.finish()
+        }
+        None => {
+            writer.write_all(stats_table(&stats).as_bytes())?;
+            writer.flush()?;
+            Ok(())
+        }
+    }
+}
+
+fn collect_stats(
+    mut reader: Reader<impl Read>,
+    headers: &StringRecord,
+    top: usize,
+) -> Result<Vec<ColumnStats>> {
+    let mut columns = headers
+        .iter()
+        .map(|_| ColumnAccumulator::default())
+        .collect::<Vec<_>>();
+    let mut record = StringRecord::new();
+    while reader.read_record(&mut record)? {
+        for (column, cell) in columns.iter_mut().zip(record.iter()) {
+            column.push(cell);
+        }
+    }
+    Ok(headers
+        .iter()
+        .zip(columns)
+        .map(|(name, column)| column.finish(name, top))
+        .collect())
+}
+
+impl ColumnAccumulator {
+    fn push(&mut self, cell: &str) {
+        self.count += 1;
+        let cell_type = ColumnType::detect(cell);
+        self.column_type = Some(match self.column_type {
+            Some(t) => t.merge(cell_type),
+            None => cell_type,
+        });
+        if cell_type == ColumnType::Null {
+            self.nulls += 1;
+            return;
+        }
+        if let Ok(v) = cell.trim().parse::<f64>() {
+            if v.is_finite() {
+                self.numeric += 1;
+                let delta = v - self.mean;
+                self.mean += delta / self.numeric as f64;
+                self.m2 += delta * (v - self.mean);
+            }
+        }
+        *self.values.entry(cell.to_string()).or_default() += 1;
+    }
+
+    fn finish(self, name: &str, top: usize) -> ColumnStats {
+        let column_type = self.column_type.unwrap_or(ColumnType::Null);
+        let numeric = matches!(column_type, ColumnType::Integer | ColumnType::Float);
+        let min = self
+            .values
+            .keys()
+            .min_by(|a, b| compare_cells(a, b))
+            .cloned();
+        let max = self
+            .values
+            .keys()
+            .max_by(|a, b| compare_cells(a, b))
+            .cloned();
+
+        let mut values = self.values.into_iter().collect::<Vec<_>>();
+        values.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        let distinct = values.len();
+        let top = values
+            .into_iter()
+            .take(top)
+            .map(|(value, count)| ValueCount { value, count })
+            .collect();
+
+        ColumnStats {
+            name: name.to_string(),
+            column_type,
+            count: self.count,
+            nulls: self.nulls,
+            null_ratio: if self.count == 0 {
+                0.0
+            } else {
+                self.nulls as f64 / self.count as f64
+            },
+            distinct,
+            min,
+            max,
+            mean: numeric.then_some(self.mean),
+            stddev: (numeric && self.numeric > 1)
+                .then(|| (self.m2 / (self.numeric - 1) as f64).sqrt()),
+            top,
+        }
+    }
+}
+
+fn stats_table(stats: &[ColumnStats]) -> String {
+    let headers = [
+        "column", "type", "count", "null%", "distinct", "min", "max", "mean", "stddev", "top",
+    ]
+    .map(String::from);
+    let float = |v: Option<f64>| v.map(|v| format!("{:.4}", v)).unwrap_or_default();
+    let rows = stats
+        .iter()
+        .map(|s| {
+            let top = s
+                .top
+                .iter()
+                .map(|v| format!("{} ({})", v.value, v.count))
+                .collect::<Vec<_>>()
+                .join(", ");
+            vec![
+                s.name.clone(),
+                s.column_type.to_string(),
+                s.count.to_string(),
+                format!("{:.1}", s.null_ratio * 100.0),
+                s.distinct.to_string(),
+                s.min.clone().unwrap_or_default(),
+                s.max.clone().unwrap_or_default(),
+                float(s.mean),
+                float(s.stddev),
+                top,
+            ]
+        })
+        .collect::<Vec<_>>();
+    render_table(&headers, &rows)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_collect_stats() {
+        let data = "id,score,team\n1,10,a\n2,,b\n3,20,a\n4,30,a\n";
+        let mut reader = Reader::from_reader(data.as_bytes());
+        let headers = reader.headers().unwrap().clone();
+        let stats = collect_stats(reader, &headers, 1).unwrap();
+
+        let score = &stats[1];
+        assert_eq!(score.column_type, ColumnType::Integer);
+        assert_eq!(score.count, 4);
+        assert_eq!(score.nulls, 1);
+        assert_eq!(score.distinct, 3);
+        assert_eq!(score.min.as_deref(), Some("10"));
+        assert_eq!(score.max.as_deref(), Some("30"));
+        assert_eq!(score.mean, Some(20.0));
+        assert_eq!(score.stddev, Some(10.0));
+
+        let team = &stats[2];
+        assert_eq!(team.column_type, ColumnType::String);
+        assert_eq!(team.mean, None);
+        assert_eq!(team.top[0].value, "a");
+        assert_eq!(team.top[0].count, 3);
+    }
+}
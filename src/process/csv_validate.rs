@@ -0,0 +1,259 @@
+use super::csv_reader::open_csv;
+use super::data_to_output;
+use super::record::infer::ColumnType;
+use super::record::{create_record_writer, load_document};
+use crate::cli::{CsvReadOpts, OutputFormat};
+use anyhow::{bail, Result};
+use csv::{Reader, StringRecord};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::fmt::Write as _;
+use std::io::Read;
+
+#[derive(Debug, Deserialize)]
+pub struct CsvSchema {
+    pub columns: Vec<ColumnSchema>,
+    // 为 true 时不允许出现 schema 中未声明的列
+    #[serde(default)]
+    pub strict: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ColumnSchema {
+    pub name: String,
+    #[serde(rename = "type", default = "default_column_type")]
+    pub column_type: ColumnType,
+    #[serde(default)]
+    pub required: bool,
+    pub pattern: Option<String>,
+    #[serde(rename = "enum")]
+    pub values: Option<Vec<String>>,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+}
+
+#[derive(Debug, Serialize, PartialEq)]
+pub struct Violation {
+    pub line: u64,
+    pub column: usize,
+    pub name: String,
+    pub value: String,
+    pub message: String,
+}
+
+struct ColumnRule<'a> {
+    index: usize,
+    schema: &'a ColumnSchema,
+    pattern: Option<Regex>,
+}
+
+fn default_column_type() -> ColumnType {
+    ColumnType::String
+}
+
+pub fn process_csv_validate(
+    input: &str,
+    read: &CsvReadOpts,
+    schema: &str,
+    output: &str,
+    format: Option<OutputFormat>,
+) -> Result<()> {
+    let schema: CsvSchema = load_document(schema)?;
+    let (reader, headers) = open_csv(input, read)?;
+    let violations = validate_csv(reader, &headers, &schema)?;
+    let mut writer = data_to_output(output)?;
+    match format {
+        Some(format) => {
+            let mut writer = create_record_writer(format, writer)?;
+            for violation in &violations {
+                writer.write_record(&serde_json::to_value(violation)?)?;
+            }
+            writer.finish()?;
+        }
+        None => {
+            writer.write_all(violation_report(&violations).as_bytes())?;
+            writer.flush()?;
+        }
+    }
+    if !violations.is_empty() {
+        bail!(
+            "CSV validation failed with {} violation(s)",
+            violations.len()
+        );
+    }
+    Ok(())
+}
+
+fn validate_csv(
+    mut reader: Reader<impl Read>,
+    headers: &StringRecord,
+    schema: &CsvSchema,
+) -> Result<Vec<Violation>> {
+    let mut violations = Vec::new();
+    let header_line = if reader.has_headers() { 1 } else { 0 };
+    let header_violation = |column: usize, name: &str, message: &str| Violation {
+        line: header_line,
+        column,
+        name: name.to_string(),
+        value: String::new(),
+        message: message.to_string(),
+    };
+
+    let mut rules = Vec::new();
+    for column in &schema.columns {
+        match headers.iter().position(|h| h == column.name) {
+            Some(index) => {
+                let pattern = match &column.pattern {
+                    // 整个单元格都需要匹配
+                    Some(p) => Some(Regex::new(&format!("^(?:{})$", p))?),
+                    None => None,
+                };
+                rules.push(ColumnRule {
+                    index,
+                    schema: column,
+                    pattern,
+                });
+            }
+            None => violations.push(header_violation(0, &column.name, "missing column")),
+        }
+    }
+    if schema.strict {
+        for (i, name) in headers.iter().enumerate() {
+            if !schema.columns.iter().any(|c| c.name == name) {
+                violations.push(header_violation(i + 1, name, "undeclared column"));
+            }
+        }
+    }
+
+    let mut record = StringRecord::new();
+    while reader.read_record(&mut record)? {
+        let line = record.position().map(|p| p.line()).unwrap_or_default();
+        for rule in &rules {
+            let value = record.get(rule.index).unwrap_or_default();
+            if let Some(message) = rule.check(value) {
+                violations.push(Violation {
+                    line,
+                    column: rule.index + 1,
+                    name: rule.schema.name.clone(),
+                    value: value.to_string(),
+                    message,
+                });
+            }
+        }
+    }
+    Ok(violations)
+}
+
+impl ColumnRule<'_> {
+    fn check(&self, value: &str) -> Option<String> {
+        let schema = self.schema;
+        let actual = ColumnType::detect(value);
+        if actual == ColumnType::Null {
+            return schema
+                .required
+                .then(|| "required value is empty".to_string());
+        }
+        let type_ok = match schema.column_type {
+            ColumnType::String => true,
+            ColumnType::Float => matches!(actual, ColumnType::Integer | ColumnType::Float),
+            expected => actual == expected,
+        };
+        if !type_ok {
+            return Some(format!("expected {}, found {}", schema.column_type, actual));
+        }
+        if let Some(pattern) = &self.pattern {
+            if !pattern.is_match(value) {
+                return Some(format!("does not match pattern {:?}", pattern.as_str()));
+            }
+        }
+        if let Some(values) = &schema.values {
+            if !values.iter().any(|v| v == value) {
+                return Some(format!("not one of {:?}", values));
+            }
+        }
+        if schema.min.is_some() || schema.max.is_some() {
+            let Ok(number) = value.trim().parse::<f64>() else {
+                return Some("range check requires a number".to_string());
+            };
+            if let Some(min) = schema.min.filter(|min| number < *min) {
+                return Some(format!("less than minimum {}", min));
+            }
+            if let Some(max) = schema.max.filter(|max| number > *max) {
+                return Some(format!("greater than maximum {}", max));
+            }
+        }
+        None
+    }
+}
+
+fn violation_report(violations: &[Violation]) -> String {
+    let mut out = String::new();
+    for v in violations {
+        let value = if v.value.is_empty() {
+            String::new()
+        } else {
+            format!(" {}", Value::String(v.value.clone()))
+        };
+        let _ = writeln!(
+            out,
+            "line {}, column {} ({}){}: {}",
+            v.line, v.column, v.name, value, v.message
+        );
+    }
+    if violations.is_empty() {
+        out.push_str("ok\n");
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_validate_csv() {
+        let schema: CsvSchema = serde_yaml::from_str(
+            r#"
+strict: true
+columns:
+  - name: id
+    type: integer
+    required: true
+    min: 1
+  - name: email
+    pattern: '[^@]+@[^@]+'
+  - name: team
+    enum: [a, b]
+  - name: score
+    type: float
+    max: 100
+  - name: missing
+"#,
+        )
+        .unwrap();
+        let data = "id,email,team,score,extra\n1,x@y.z,a,99.5,\n,bad,c,1e3,\n0,,b,abc,\n";
+        let mut reader = Reader::from_reader(data.as_bytes());
+        let headers = reader.headers().unwrap().clone();
+        let violations = validate_csv(reader, &headers, &schema).unwrap();
+        let summary = violations
+            .iter()
+            .map(|v| (v.line, v.column, v.message.as_str()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            summary,
+            vec![
+                (1, 0, "missing column"),
+                (1, 5, "undeclared column"),
+                (3, 1, "required value is empty"),
+                (3, 2, "does not match pattern \"^(?:[^@]+@[^@]+)$\""),
+                (3, 3, "not one of [\"a\", \"b\"]"),
+                (3, 4, "greater than maximum 100"),
+                (4, 1, "less than minimum 1"),
+                (4, 4, "expected float, found string"),
+            ]
+        );
+        assert!(violation_report(&violations)
+            .starts_with("line 1, column 0 (missing): missing column\n"));
+    }
+}
@@ -0,0 +1,217 @@
+use super::csv_index::load_index;
+use super::csv_reader::open_csv;
+use super::table::render_table_fit;
+use crate::cli::{CsvReadOpts, RowSelection};
+use anyhow::Result;
+use csv::{Reader, StringRecord};
+use std::collections::VecDeque;
+use std::io::{IsTerminal, Read, Write};
+
+struct TableView {
+    headers: Vec<String>,
+    page_size: usize,
+    max_width: Option<usize>,
+    max_cell_width: usize,
+}
+
+pub fn process_csv_view(
+    input: &str,
+    read: &CsvReadOpts,
+    selection: RowSelection,
+    page_size: usize,
+    width: Option<usize>,
+    max_cell_width: usize,
+) -> Result<()> {
+    // 有索引时 range 与 tail 直接定位到起始行附近，不需要从头读取
+    let (reader, headers, first, selection) = match (load_index(input, read)?, selection) {
+        (Some(index), RowSelection::Range(start, end)) => {
+            let indexed = index.seek(input, start)?;
+            let selection = RowSelection::Range(start, end);
+            (indexed.reader, indexed.headers, indexed.first, selection)
+        }
+        (Some(index), RowSelection::Tail(n)) => {
+            let start = index.rows().saturating_sub(n) + 1;
+            let indexed = index.seek(input, start)?;
+            let selection = RowSelection::Range(start, index.rows());
+            (indexed.reader, indexed.headers, indexed.first, selection)
+        }
+        (_, selection) => {
+            let (reader, headers) = open_csv(input, read)?;
+            (reader, headers, 1, selection)
+        }
+    };
+    let view = TableView {
+        headers: std::iter::once("#")
+            .chain(headers.iter())
+            .map(String::from)
+            .collect(),
+        page_size: page_size.max(1),
+        max_width: width.or_else(terminal_width),
+        max_cell_width,
+    };
+    // 只有输出到终端且 stdin 未被用作输入时才分页等待
+    let interactive = input != "-" && std::io::stdout().is_terminal();
+    let mut stdout = std::io::stdout().lock();
+    view.show(
+        select_rows(reader, selection, first),
+        &mut stdout,
+        interactive,
+    )
+}
+
+fn terminal_width() -> Option<usize> {
+    if let Some((terminal_size::Width(w), _)) = terminal_size::terminal_size() {
+        return Some(w as usize);
+    }
+    std::env::var("COLUMNS").ok()?.parse().ok()
+}
+
+// 按 head/tail/range 选择行，行号从 1 开始，first 为 reader 第一行的行号；
+// tail 只保留最后 n 行，不会加载整个文件
+fn select_rows<'a>(
+    reader: Reader<impl Read + 'a>,
+    selection: RowSelection,
+    first: usize,
+) -> Box<dyn Iterator<Item = Result<(usize, StringRecord)>> + 'a> {
+    let rows = reader
+        .into_records()
+        .enumerate()
+        .map(move |(i, record)| Ok((i + first, record?)));
+    match selection {
+        RowSelection::All => Box::new(rows),
+        RowSelection::Head(n) => Box::new(rows.take(n)),
+        RowSelection::Range(start, end) => Box::new(
+            rows.skip(start.saturating_sub(first))
+                .take((end + 1).saturating_sub(start.max(first))),
+        ),
+        RowSelection::Tail(n) => {
+            // n 来自用户输入，不预先分配，避免 --tail 很大时按 n 申请内存
+            let mut tail = VecDeque::new();
+            for row in rows {
+                if n == 0 {
+                    break;
+                }
+                if tail.len() == n {
+                    tail.pop_front();
+                }
+                tail.push_back(row);
+            }
+            Box::new(tail.into_iter())
+        }
+    }
+}
+
+impl TableView {
+    fn show(
+        &self,
+        rows: impl Iterator<Item = Result<(usize, StringRecord)>>,
+        writer: &mut dyn Write,
+        interactive: bool,
+    ) -> Result<()> {
+        let mut page = Vec::new();
+        let mut rows = rows.peekable();
+        while let Some(row) = rows.next() {
+            let (line, record) = row?;
+            page.push(
+                std::iter::once(line.to_string())
+                    .chain(record.iter().map(String::from))
+                    .collect::<Vec<_>>(),
+            );
+            if page.len() < self.page_size {
+                continue;
+            }
+            writer.write_all(self.render(&page).as_bytes())?;
+            writer.flush()?;
+            page.clear();
+            if interactive && rows.peek().is_some() && !wait_next_page()? {
+                return Ok(());
+            }
+        }
+        if !page.is_empty() {
+            writer.write_all(self.render(&page).as_bytes())?;
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    fn render(&self, page: &[Vec<String>]) -> String {
+        render_table_fit(
+            &self.headers,
+            page,
+            self.max_width,
+            Some(self.max_cell_width),
+        )
+    }
+}
+
+fn wait_next_page() -> Result<bool> {
+    eprint!("-- more (enter to continue, q to quit) --");
+    let mut line = String::new();
+    std::io::stdin().read_line(&mut line)?;
+    Ok(!line.trim().eq_ignore_ascii_case("q"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn view(selection: RowSelection, page_size: usize) -> String {
+        let mut reader = Reader::from_reader("n,v\n1,a\n2,b\n3,c\n4,d\n".as_bytes());
+        let headers = reader.headers().unwrap().clone();
+        let view = TableView {
+            headers: std::iter::once("#")
+                .chain(headers.iter())
+                .map(String::from)
+                .collect(),
+            page_size,
+            max_width: None,
+            max_cell_width: 10,
+        };
+        let mut buf = Vec::new();
+        view.show(select_rows(reader, selection, 1), &mut buf, false)
+            .unwrap();
+        String::from_utf8(buf).unwrap()
+    }
+
+    #[test]
+    fn test_view_selection() {
+        assert_eq!(
+            view(RowSelection::Head(1), 10),
+            "# | n | v\n- | - | -\n1 | 1 | a\n"
+        );
+        assert_eq!(
+            view(RowSelection::Tail(1), 10),
+            "# | n | v\n- | - | -\n4 | 4 | d\n"
+        );
+        assert_eq!(
+            view(RowSelection::Range(2, 3), 10),
+            "# | n | v\n- | - | -\n2 | 2 | b\n3 | 3 | c\n"
+        );
+    }
+
+    #[test]
+    fn test_select_rows_from_offset() {
+        // 通过索引定位后 reader 从第 3 行开始
+        let reader = csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_reader("3,c\n4,d\n5,e\n".as_bytes());
+        let rows = select_rows(reader, RowSelection::Range(4, 4), 3)
+            .map(|row| row.unwrap().0)
+            .collect::<Vec<_>>();
+        assert_eq!(rows, vec![4]);
+    }
+
+    #[test]
+    fn test_view_huge_tail_and_page_size() {
+        let out = view(RowSelection::Tail(usize::MAX), usize::MAX);
+        assert!(out.starts_with("# | n | v\n- | - | -\n1 | 1 | a\n"));
+        assert!(out.ends_with("4 | 4 | d\n"));
+    }
+
+    #[test]
+    fn test_view_pages() {
+        let out = view(RowSelection::All, 3);
+        assert_eq!(out.matches("# | n | v").count(), 2);
+        assert!(out.ends_with("4 | 4 | d\n"));
+    }
+}
@@ -1,37 +1,479 @@
+use super::record::load_document;
 use ::rand;
-use anyhow::Result;
+use anyhow::{bail, Result};
 use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::Deserialize;
+use std::collections::{BTreeSet, HashSet};
+use std::fmt;
 
-pub fn process_gen_pass(
-    length: usize,
-    count: usize,
-    uppercase: bool,
-    lowercase: bool,
-    number: bool,
-    symbol: bool,
-) -> Result<Vec<String>> {
+const UPPERCASE: &str = "ABCDEFGHIJKLMNOPQRSTUVWXYZ";
+const LOWERCASE: &str = "abcdefghijklmnopqrstuvwxyz";
+const NUMBERS: &str = "0123456789";
+const DEFAULT_SYMBOLS: &str = "!@#$%^&*()-_=+";
+const AMBIGUOUS: &str = "0O1lI";
+// no_repeat 通过重新生成来满足，字符集过小时避免无限重试
+const MAX_ATTEMPTS: usize = 1000;
+
+// 未指定 --wordlist 时使用的 EFF 长词表（7776 词，每词约 12.9 bits）
+pub const EFF_WORDLIST: &str = include_str!("../../assets/eff_large_wordlist.txt");
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum CharClass {
+    Uppercase,
+    Lowercase,
+    Number,
+    Symbol,
+}
+
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PasswordPolicy {
+    pub length: usize,
+    pub uppercase: bool,
+    pub lowercase: bool,
+    pub number: bool,
+    pub symbol: bool,
+    // 每类字符至少出现的次数，只对启用的字符类生效
+    pub min_uppercase: usize,
+    pub min_lowercase: usize,
+    pub min_number: usize,
+    pub min_symbol: usize,
+    pub symbols: String,
+    pub exclude_ambiguous: bool,
+    // 不允许相邻两个字符相同
+    pub no_repeat: bool,
+}
+
+pub fn process_gen_pass(policy: &PasswordPolicy, count: usize) -> Result<Vec<String>> {
+    let classes = policy.class_charsets()?;
     let mut rng = rand::thread_rng();
-    let mut charset = Vec::new();
-    if uppercase {
-        charset.extend_from_slice(b"ABCDEFGHIJKLMNOPQRSTUVWXYZ");
+    (0..count)
+        .map(|_| policy.generate(&classes, &mut rng))
+        .collect()
+}
+
+pub fn load_password_policy(path: &str) -> Result<PasswordPolicy> {
+    load_document(path)
+}
+
+impl CharClass {
+    pub const ALL: [CharClass; 4] = [
+        CharClass::Uppercase,
+        CharClass::Lowercase,
+        CharClass::Number,
+        CharClass::Symbol,
+    ];
+
+    // 字母和数字以外的字符都算作符号
+    pub fn of(c: char) -> Self {
+        if c.is_uppercase() {
+            CharClass::Uppercase
+        } else if c.is_lowercase() {
+            CharClass::Lowercase
+        } else if c.is_numeric() {
+            CharClass::Number
+        } else {
+            CharClass::Symbol
+        }
+    }
+}
+
+impl fmt::Display for CharClass {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", Into::<&str>::into(*self))
+    }
+}
+
+impl From<CharClass> for &'static str {
+    fn from(class: CharClass) -> Self {
+        match class {
+            CharClass::Uppercase => "uppercase",
+            CharClass::Lowercase => "lowercase",
+            CharClass::Number => "number",
+            CharClass::Symbol => "symbol",
+        }
+    }
+}
+
+impl Default for PasswordPolicy {
+    fn default() -> Self {
+        PasswordPolicy {
+            length: 16,
+            uppercase: true,
+            lowercase: true,
+            number: true,
+            symbol: true,
+            min_uppercase: 1,
+            min_lowercase: 1,
+            min_number: 1,
+            min_symbol: 1,
+            symbols: DEFAULT_SYMBOLS.to_string(),
+            exclude_ambiguous: false,
+            no_repeat: false,
+        }
+    }
+}
+
+impl PasswordPolicy {
+    pub fn enabled(&self, class: CharClass) -> bool {
+        match class {
+            CharClass::Uppercase => self.uppercase,
+            CharClass::Lowercase => self.lowercase,
+            CharClass::Number => self.number,
+            CharClass::Symbol => self.symbol,
+        }
+    }
+
+    pub fn min_count(&self, class: CharClass) -> usize {
+        match class {
+            CharClass::Uppercase => self.min_uppercase,
+            CharClass::Lowercase => self.min_lowercase,
+            CharClass::Number => self.min_number,
+            CharClass::Symbol => self.min_symbol,
+        }
+    }
+
+    pub fn charset(&self, class: CharClass) -> Vec<char> {
+        let chars = match class {
+            CharClass::Uppercase => UPPERCASE,
+            CharClass::Lowercase => LOWERCASE,
+            CharClass::Number => NUMBERS,
+            CharClass::Symbol => &self.symbols,
+        };
+        let mut seen = HashSet::new();
+        chars
+            .chars()
+            .filter(|c| !(self.exclude_ambiguous && AMBIGUOUS.contains(*c)))
+            .filter(|c| seen.insert(*c))
+            .collect()
+    }
+
+    // 检查已有的密码是否符合策略，与生成密码时使用同样的字符分类
+    pub fn violations(&self, password: &str) -> Vec<String> {
+        let chars = password.chars().collect::<Vec<_>>();
+        let mut violations = Vec::new();
+        if chars.len() < self.length {
+            violations.push(format!(
+                "shorter than {} characters, found {}",
+                self.length,
+                chars.len()
+            ));
+        }
+        for class in CharClass::ALL {
+            let count = chars.iter().filter(|c| CharClass::of(**c) == class).count();
+            if !self.enabled(class) && count > 0 {
+                violations.push(format!("contains {} characters", class));
+            } else if self.enabled(class) && count < self.min_count(class) {
+                violations.push(format!(
+                    "needs at least {} {} characters, found {}",
+                    self.min_count(class),
+                    class,
+                    count
+                ));
+            }
+        }
+        let charset = CharClass::ALL
+            .iter()
+            .filter(|class| self.enabled(**class))
+            .flat_map(|class| self.charset(*class))
+            .collect::<HashSet<_>>();
+        let outside = chars
+            .iter()
+            .filter(|c| self.enabled(CharClass::of(**c)) && !charset.contains(*c))
+            .collect::<BTreeSet<_>>();
+        if !outside.is_empty() {
+            let outside = outside.into_iter().collect::<Vec<_>>();
+            violations.push(format!("characters {:?} are not allowed", outside));
+        }
+        if self.no_repeat && chars.windows(2).any(|w| w[0] == w[1]) {
+            violations.push("repeats a character consecutively".to_string());
+        }
+        violations
     }
-    if lowercase {
-        charset.extend_from_slice(b"abcdefghijklmnopqrstuvwxyz");
+
+    // 按长度和合并后的字符集大小估算，最少次数等约束带来的损失忽略不计
+    pub fn entropy(&self) -> Result<f64> {
+        let size = self
+            .class_charsets()?
+            .iter()
+            .map(|(chars, _)| chars.len())
+            .sum::<usize>();
+        Ok(self.length as f64 * (size as f64).log2())
     }
-    if number {
-        charset.extend_from_slice(b"0123456789");
+
+    // 启用的字符类及其最少次数，同时检查策略能否被满足
+    fn class_charsets(&self) -> Result<Vec<(Vec<char>, usize)>> {
+        let mut classes = Vec::new();
+        for class in CharClass::ALL {
+            if !self.enabled(class) {
+                continue;
+            }
+            let chars = self.charset(class);
+            if chars.is_empty() {
+                bail!("Character class {:?} has no characters left", class);
+            }
+            // 自定义符号集中的字母和数字会让生成的密码违反策略本身的字符类检查
+            if let Some(c) = chars.iter().find(|c| CharClass::of(**c) != class) {
+                bail!(
+                    "Character {:?} in the {} set is not a {} character",
+                    c,
+                    class,
+                    class
+                );
+            }
+            classes.push((chars, self.min_count(class)));
+        }
+        if classes.is_empty() {
+            bail!("Password policy must enable at least one character class");
+        }
+        let required = classes.iter().map(|(_, min)| min).sum::<usize>();
+        if required > self.length {
+            bail!(
+                "Password length {} is shorter than the {} required characters",
+                self.length,
+                required
+            );
+        }
+        let size = classes.iter().map(|(chars, _)| chars.len()).sum::<usize>();
+        if self.no_repeat && self.length > 1 && size < 2 {
+            bail!("no_repeat needs at least 2 distinct characters");
+        }
+        Ok(classes)
     }
-    if symbol {
-        charset.extend_from_slice(b"!@#$%^&*()-_=+");
+
+    // 先放入每类要求的最少字符，剩余位置从合并的字符集中选取，最后打乱顺序
+    fn generate(&self, classes: &[(Vec<char>, usize)], rng: &mut impl Rng) -> Result<String> {
+        let all = classes
+            .iter()
+            .flat_map(|(chars, _)| chars.iter().copied())
+            .collect::<Vec<_>>();
+        for _ in 0..MAX_ATTEMPTS {
+            let mut password: Vec<char> = Vec::with_capacity(self.length);
+            for (chars, min) in classes {
+                password.extend((0..*min).filter_map(|_| chars.choose(rng).copied()));
+            }
+            while password.len() < self.length {
+                password.extend(all.choose(rng).copied());
+            }
+            password.shuffle(rng);
+            if !self.no_repeat || password.windows(2).all(|w| w[0] != w[1]) {
+                return Ok(password.into_iter().collect());
+            }
+        }
+        bail!("Could not generate a password satisfying the policy")
     }
-    let mut passwords = Vec::with_capacity(count);
+}
+
+// 每个单词独立从词表中均匀选取，熵为 words * log2(词表大小)；
+// 注入的每个数字随机追加到某个单词后，额外增加 log2(10 * words)
+pub fn process_gen_passphrase(
+    wordlist: &[String],
+    words: usize,
+    count: usize,
+    separator: &str,
+    capitalize: bool,
+    digits: usize,
+) -> Result<(Vec<String>, f64)> {
+    if wordlist.len() < 2 {
+        bail!("Wordlist must contain at least 2 distinct words");
+    }
+    if words == 0 {
+        bail!("Passphrase must contain at least 1 word");
+    }
+    let mut rng = rand::thread_rng();
+    let mut phrases = Vec::with_capacity(count);
     for _ in 0..count {
-        let mut password = String::with_capacity(length);
-        for _ in 0..length {
-            let c = charset.choose(&mut rng).expect("chars want be empty");
-            password.push(*c as char);
+        let mut chosen = (0..words)
+            .map(|_| {
+                let word = wordlist.choose(&mut rng).expect("wordlist won't be empty");
+                if capitalize {
+                    capitalize_word(word)
+                } else {
+                    word.clone()
+                }
+            })
+            .collect::<Vec<_>>();
+        for _ in 0..digits {
+            let i = rng.gen_range(0..words);
+            chosen[i].push(char::from(b'0' + rng.gen_range(0..10)));
+        }
+        phrases.push(chosen.join(separator));
+    }
+    let entropy = words as f64 * (wordlist.len() as f64).log2()
+        + digits as f64 * (10.0 * words as f64).log2();
+    Ok((phrases, entropy))
+}
+
+// 支持 EFF 词表格式（每行为骰子编号 + tab + 单词）以及每行一个单词的普通词表
+pub fn load_wordlist(content: &str) -> Vec<String> {
+    let mut seen = HashSet::new();
+    content
+        .lines()
+        .filter_map(|line| {
+            let mut parts = line.split_whitespace();
+            let first = parts.next()?;
+            match parts.next() {
+                Some(word) if first.bytes().all(|b| (b'1'..=b'6').contains(&b)) => Some(word),
+                _ => Some(first),
+            }
+        })
+        .filter(|word| seen.insert(word.to_string()))
+        .map(String::from)
+        .collect()
+}
+
+fn capitalize_word(word: &str) -> String {
+    let mut chars = word.chars();
+    match chars.next() {
+        Some(first) => first.to_uppercase().chain(chars).collect(),
+        None => String::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_gen_pass_policy() {
+        let policy = PasswordPolicy {
+            length: 12,
+            symbol: false,
+            min_number: 4,
+            min_uppercase: 3,
+            exclude_ambiguous: true,
+            no_repeat: true,
+            ..Default::default()
+        };
+        for password in process_gen_pass(&policy, 50).unwrap() {
+            let chars = password.chars().collect::<Vec<_>>();
+            assert_eq!(chars.len(), 12);
+            let count = |class| chars.iter().filter(|c| CharClass::of(**c) == class).count();
+            assert!(count(CharClass::Number) >= 4);
+            assert!(count(CharClass::Uppercase) >= 3);
+            assert!(count(CharClass::Lowercase) >= 1);
+            assert_eq!(count(CharClass::Symbol), 0);
+            assert!(!chars.iter().any(|c| AMBIGUOUS.contains(*c)));
+            assert!(chars.windows(2).all(|w| w[0] != w[1]));
+        }
+        // 26 + 26 + 10 - 5 个可用字符
+        let entropy = policy.entropy().unwrap();
+        assert!((entropy - 12.0 * 57f64.log2()).abs() < 1e-9);
+
+        let policy = PasswordPolicy {
+            length: 4,
+            uppercase: false,
+            lowercase: false,
+            number: false,
+            symbols: "#".to_string(),
+            min_symbol: 4,
+            ..Default::default()
+        };
+        assert_eq!(process_gen_pass(&policy, 1).unwrap(), vec!["####"]);
+        let invalid = [
+            PasswordPolicy {
+                no_repeat: true,
+                ..policy.clone()
+            },
+            PasswordPolicy {
+                symbol: false,
+                ..policy.clone()
+            },
+            PasswordPolicy {
+                length: 3,
+                ..policy.clone()
+            },
+            PasswordPolicy {
+                symbols: String::new(),
+                ..policy.clone()
+            },
+            PasswordPolicy {
+                symbols: "#a".to_string(),
+                ..policy.clone()
+            },
+            PasswordPolicy {
+                symbols: "#7".to_string(),
+                ..policy.clone()
+            },
+        ];
+        for policy in invalid {
+            assert!(process_gen_pass(&policy, 1).is_err());
         }
-        passwords.push(password);
     }
-    Ok(passwords)
+
+    #[test]
+    fn test_policy_violations() {
+        let policy = PasswordPolicy {
+            length: 10,
+            symbol: false,
+            exclude_ambiguous: true,
+            no_repeat: true,
+            ..Default::default()
+        };
+        assert!(policy.violations("Xk7mPq2wZr").is_empty());
+        assert_eq!(
+            policy.violations("aa1Il!1"),
+            vec![
+                "shorter than 10 characters, found 7",
+                "contains symbol characters",
+                "characters ['1', 'I', 'l'] are not allowed",
+                "repeats a character consecutively",
+            ]
+        );
+        assert_eq!(
+            policy.violations("abcdefghjk"),
+            vec![
+                "needs at least 1 uppercase characters, found 0",
+                "needs at least 1 number characters, found 0",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_policy_profile() {
+        let policy: PasswordPolicy =
+            toml::from_str("length = 24\nsymbols = \"!?\"\nno_repeat = true\n").unwrap();
+        assert_eq!(policy.length, 24);
+        assert_eq!(policy.charset(CharClass::Symbol), vec!['!', '?']);
+        assert!(policy.uppercase && policy.no_repeat);
+        assert!(toml::from_str::<PasswordPolicy>("lenght = 24").is_err());
+    }
+
+    #[test]
+    fn test_load_wordlist() {
+        let words = load_wordlist("11111\tabacus\n11112\tabdomen\n\nzebra\nzebra\n");
+        assert_eq!(words, vec!["abacus", "abdomen", "zebra"]);
+
+        let words = load_wordlist(EFF_WORDLIST);
+        assert_eq!(words.len(), 7776);
+        let (_, entropy) = process_gen_passphrase(&words, 6, 1, "-", false, 0).unwrap();
+        assert!((entropy - 77.5).abs() < 0.1);
+    }
+
+    #[test]
+    fn test_gen_passphrase() {
+        let wordlist = ["alpha", "bravo", "charlie", "delta"]
+            .iter()
+            .map(|w| w.to_string())
+            .collect::<Vec<_>>();
+        let (phrases, entropy) = process_gen_passphrase(&wordlist, 3, 5, ".", true, 0).unwrap();
+        assert_eq!(phrases.len(), 5);
+        assert_eq!(entropy, 6.0);
+        for phrase in &phrases {
+            let words = phrase.split('.').collect::<Vec<_>>();
+            assert_eq!(words.len(), 3);
+            assert!(
+                words
+                    .iter()
+                    .all(|w| wordlist.contains(&w.to_lowercase())
+                        && w.starts_with(char::is_uppercase))
+            );
+        }
+
+        let (phrases, entropy) = process_gen_passphrase(&wordlist, 2, 1, " ", false, 1).unwrap();
+        assert_eq!(phrases[0].chars().filter(char::is_ascii_digit).count(), 1);
+        assert!((entropy - (4.0 + 20f64.log2())).abs() < 1e-9);
+        assert!(process_gen_passphrase(&wordlist[..1], 3, 1, "-", false, 0).is_err());
+    }
 }
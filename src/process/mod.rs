@@ -1,11 +1,36 @@
+use anyhow::anyhow;
+use csv::StringRecord;
 use std::fs::File;
-use std::io::Read;
+use std::io::{BufWriter, Read, Write};
 
 pub mod base64_ed;
+pub mod csv_concat;
 pub mod csv_convert;
+pub mod csv_crypt;
+pub mod csv_diff;
+pub mod csv_fake;
+pub mod csv_groupby;
+pub mod csv_index;
+pub mod csv_join;
+pub mod csv_lint;
+pub mod csv_mask;
+pub mod csv_query;
+mod csv_reader;
+pub mod csv_reverse;
+pub mod csv_sample;
+pub mod csv_schema;
+pub mod csv_split;
+pub mod csv_stats;
+pub mod csv_validate;
+pub mod csv_view;
 pub mod gen_pass;
 pub mod http_serve;
 pub(crate) mod jwt;
+pub mod pass_strength;
+mod record;
+mod spreadsheet;
+mod sql;
+mod table;
 mod text;
 pub mod text_op;
 
@@ -17,3 +42,34 @@ fn data_from_input(input: &str) -> anyhow::Result<Box<dyn Read>> {
     };
     Ok(reader)
 }
+
+fn data_to_output(output: &str) -> anyhow::Result<Box<dyn Write>> {
+    let writer: Box<dyn Write> = if output == "-" {
+        Box::new(BufWriter::new(std::io::stdout()))
+    } else {
+        Box::new(BufWriter::new(File::create(output)?))
+    };
+    Ok(writer)
+}
+
+fn column_index(headers: &StringRecord, name: &str) -> anyhow::Result<usize> {
+    headers
+        .iter()
+        .position(|h| h == name)
+        .ok_or_else(|| anyhow!("Column {:?} not found", name))
+}
+
+fn column_indexes(headers: &StringRecord, names: &[String]) -> anyhow::Result<Vec<usize>> {
+    names
+        .iter()
+        .map(|name| column_index(headers, name))
+        .collect()
+}
+
+// 取出一行中 key 列的值，用于分组、关联和比对
+fn row_key(record: &StringRecord, index: &[usize]) -> Vec<String> {
+    index
+        .iter()
+        .map(|i| record.get(*i).unwrap_or_default().to_string())
+        .collect()
+}
@@ -0,0 +1,955 @@
+use super::gen_pass::{CharClass, PasswordPolicy, EFF_WORDLIST};
+use chrono::{Datelike, Local};
+use std::collections::{HashMap, HashSet};
+use std::fmt::Write;
+use std::sync::LazyLock;
+
+// 常用密码和常用英文单词，每行一个，按常见程度排序，名次即为猜测次数的基数；
+// genpass 内置的 EFF 词表中其余的单词排在常用英文单词之后
+const COMMON_PASSWORDS: &str = include_str!("../../assets/common_passwords.txt");
+const ENGLISH_WORDS: &str = include_str!("../../assets/english_words.txt");
+// 与 zxcvbn 一样只评估前这么多个字符，避免超长输入耗时过久；
+// 其余字符不计入猜测次数，超长密码的评分只会偏低而不会虚高
+const MAX_MATCH_LENGTH: usize = 100;
+// 键盘布局，每行相对上一行向右错开；按下 shift 时对应第二组字符
+const KEYBOARD: [&str; 4] = [
+    "`1234567890-=",
+    "qwertyuiop[]\\",
+    "asdfghjkl;'",
+    "zxcvbnm,./",
+];
+const KEYBOARD_SHIFTED: [&str; 4] = [
+    "~!@#$%^&*()_+",
+    "QWERTYUIOP{}|",
+    "ASDFGHJKL:\"",
+    "ZXCVBNM<>?",
+];
+const KEYBOARD_OFFSETS: [f64; 4] = [0.0, 1.5, 1.75, 2.25];
+// 键盘上的起始按键数和每个按键的平均相邻按键数
+const KEYBOARD_STARTS: f64 = 47.0;
+const KEYBOARD_DEGREE: f64 = 4.6;
+const L33T: [(char, &str); 14] = [
+    ('4', "a"),
+    ('@', "a"),
+    ('8', "b"),
+    ('(', "c"),
+    ('3', "e"),
+    ('6', "g"),
+    ('1', "il"),
+    ('!', "i"),
+    ('|', "il"),
+    ('0', "o"),
+    ('$', "s"),
+    ('5', "s"),
+    ('7', "t"),
+    ('2', "z"),
+];
+// 不在策略符号集中的符号按可打印 ascii 符号的数量计算
+const DEFAULT_SYMBOL_SPACE: usize = 33;
+const SEPARATORS: &str = "/\\-._ ";
+const MIN_YEAR_SPACE: f64 = 20.0;
+// 评分 0-4 的分界线
+const SCORE_THRESHOLDS: [f64; 4] = [1e3, 1e6, 1e8, 1e10];
+// 在线限速、在线不限速、离线慢哈希、离线快哈希四种场景每秒的猜测次数
+const ATTACK_RATES: [(&str, f64); 4] = [
+    ("online, throttled (100/hour)", 100.0 / 3600.0),
+    ("online, unthrottled (10/s)", 10.0),
+    ("offline, slow hash (1e4/s)", 1e4),
+    ("offline, fast hash (1e10/s)", 1e10),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Pattern {
+    Dictionary {
+        word: String,
+        rank: usize,
+        common_password: bool,
+        reversed: bool,
+        l33t: bool,
+    },
+    Spatial {
+        turns: usize,
+        shifted: usize,
+    },
+    Sequence {
+        ascending: bool,
+    },
+    Repeat {
+        unit: String,
+        count: usize,
+    },
+    Date {
+        year: i32,
+    },
+    Bruteforce,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PatternMatch {
+    pub pattern: Pattern,
+    pub token: String,
+    // 在密码中的字符位置，左闭右开
+    pub start: usize,
+    pub end: usize,
+    // 猜测次数取 log10，长密码的猜测次数会超出 f64 的范围
+    pub guesses_log10: f64,
+}
+
+struct Dictionary {
+    // 单词 -> (名次, 是否为常用密码)
+    words: HashMap<&'static str, (usize, bool)>,
+    max_len: usize,
+}
+
+static DICTIONARY: LazyLock<Dictionary> = LazyLock::new(Dictionary::new);
+
+#[derive(Debug)]
+pub struct StrengthReport {
+    pub guesses_log10: f64,
+    pub score: u8,
+    pub classes: Vec<CharClass>,
+    pub matches: Vec<PatternMatch>,
+    pub crack_times: Vec<(&'static str, f64)>,
+    pub warning: Option<String>,
+    pub suggestions: Vec<String>,
+    pub violations: Vec<String>,
+}
+
+// 仿照 zxcvbn：先找出所有可识别的模式，再选出猜测次数最少的一种拆分方式；
+// 未被模式覆盖的字符按 genpass 相同的字符类暴力猜测
+pub fn process_pass_strength(password: &str, policy: &PasswordPolicy) -> StrengthReport {
+    let chars = password.chars().collect::<Vec<_>>();
+    let (guesses_log10, matches) = estimate(&chars[..chars.len().min(MAX_MATCH_LENGTH)], policy);
+    let score = SCORE_THRESHOLDS
+        .iter()
+        .filter(|t| guesses_log10 >= t.log10())
+        .count() as u8;
+    let mut classes = chars
+        .iter()
+        .map(|c| CharClass::of(*c))
+        .collect::<HashSet<_>>()
+        .into_iter()
+        .collect::<Vec<_>>();
+    classes.sort_by_key(|class| CharClass::ALL.iter().position(|c| c == class));
+    let (warning, suggestions) = feedback(score, &matches, chars.len());
+    StrengthReport {
+        guesses_log10,
+        score,
+        classes,
+        matches,
+        crack_times: ATTACK_RATES
+            .iter()
+            // 超过一百年都显示为 centuries，限制指数避免溢出
+            .map(|(name, rate)| (*name, 10f64.powf((guesses_log10 - rate.log10()).min(300.0))))
+            .collect(),
+        warning,
+        suggestions,
+        violations: policy.violations(password),
+    }
+}
+
+impl StrengthReport {
+    pub fn render(&self, with_violations: bool) -> String {
+        let mut out = String::new();
+        let _ = writeln!(out, "Score: {}/4", self.score);
+        let _ = writeln!(
+            out,
+            "Guesses: {} (~{:.1} bits)",
+            display_guesses(self.guesses_log10),
+            self.guesses_log10 * 10f64.log2()
+        );
+        let classes = self
+            .classes
+            .iter()
+            .map(|c| c.to_string())
+            .collect::<Vec<_>>();
+        let _ = writeln!(out, "Classes: {}", classes.join(", "));
+        let _ = writeln!(out, "Crack time:");
+        for (name, seconds) in &self.crack_times {
+            let _ = writeln!(out, "  {:<30} {}", name, display_time(*seconds));
+        }
+        if !self.matches.is_empty() {
+            let _ = writeln!(out, "Patterns:");
+            for m in &self.matches {
+                let _ = writeln!(
+                    out,
+                    "  {:<12} {:<22} {}",
+                    m.pattern.name(),
+                    format!("{:?}", m.token),
+                    display_guesses(m.guesses_log10)
+                );
+            }
+        }
+        if let Some(warning) = &self.warning {
+            let _ = writeln!(out, "Warning: {}", warning);
+        }
+        for suggestion in &self.suggestions {
+            let _ = writeln!(out, "Suggestion: {}", suggestion);
+        }
+        if with_violations {
+            for violation in &self.violations {
+                let _ = writeln!(out, "Policy: {}", violation);
+            }
+        }
+        out
+    }
+}
+
+impl Pattern {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Pattern::Dictionary { .. } => "dictionary",
+            Pattern::Spatial { .. } => "spatial",
+            Pattern::Sequence { .. } => "sequence",
+            Pattern::Repeat { .. } => "repeat",
+            Pattern::Date { .. } => "date",
+            Pattern::Bruteforce => "bruteforce",
+        }
+    }
+}
+
+// 返回最少猜测次数的 log10 以及对应的拆分
+fn estimate(chars: &[char], policy: &PasswordPolicy) -> (f64, Vec<PatternMatch>) {
+    let n = chars.len();
+    if n == 0 {
+        return (0.0, Vec::new());
+    }
+    let per_char = bruteforce_cardinality(chars, policy).log10();
+    let mut candidates = find_matches(chars, policy);
+    for m in candidates.iter_mut() {
+        // 非整个密码的片段至少需要一定的猜测次数，避免拆分成大量极短的模式
+        if m.end - m.start < n {
+            let min = if m.end - m.start == 1 { 10f64 } else { 50f64 };
+            m.guesses_log10 = m.guesses_log10.max(min.log10());
+        }
+    }
+
+    // best[k] 为覆盖前 k 个字符的 (模式个数, 猜测次数之积的 log10, 上一段)，
+    // 按 l! * 乘积比较，模式越多攻击者需要尝试的组合顺序越多
+    let mut best: Vec<Option<(usize, f64, Option<usize>)>> = vec![None; n + 1];
+    best[0] = Some((0, 0.0, None));
+    let cost = |count: usize, product: f64| log10_factorial(count) + product;
+    for k in 1..=n {
+        let mut options = Vec::new();
+        for (i, m) in candidates.iter().enumerate().filter(|(_, m)| m.end == k) {
+            if let Some((count, product, _)) = best[m.start] {
+                options.push((count + 1, product + m.guesses_log10, Some(i)));
+            }
+        }
+        // 单个字符暴力猜测，与前面相邻的暴力字符合并成一段
+        if let Some((count, product, _)) = best[k - 1] {
+            let merged = bruteforce_start(&best, k - 1);
+            let count = if merged { count } else { count + 1 };
+            options.push((count, product + per_char, None));
+        }
+        best[k] = options
+            .into_iter()
+            .min_by(|a, b| cost(a.0, a.1).total_cmp(&cost(b.0, b.1)));
+    }
+
+    let mut matches = Vec::new();
+    let mut k = n;
+    while k > 0 {
+        let Some((_, _, prev)) = best[k] else {
+            break;
+        };
+        match prev {
+            Some(i) => {
+                let m = candidates[i].clone();
+                k = m.start;
+                matches.push(m);
+            }
+            None => {
+                let mut start = k - 1;
+                while start > 0 && matches!(best[start], Some((_, _, None))) {
+                    start -= 1;
+                }
+                let len = k - start;
+                matches.push(PatternMatch {
+                    pattern: Pattern::Bruteforce,
+                    token: chars[start..k].iter().collect(),
+                    start,
+                    end: k,
+                    guesses_log10: len as f64 * per_char,
+                });
+                k = start;
+            }
+        }
+    }
+    matches.reverse();
+    let (count, product, _) = best[n].unwrap_or((1, 0.0, None));
+    (cost(count, product).max(0.0), matches)
+}
+
+fn bruteforce_start(best: &[Option<(usize, f64, Option<usize>)>], k: usize) -> bool {
+    k > 0 && matches!(best[k], Some((_, _, None)))
+}
+
+// 密码中出现的每类字符都按策略中该类的字符集大小计入，与 genpass 计算熵的方式一致
+fn bruteforce_cardinality(chars: &[char], policy: &PasswordPolicy) -> f64 {
+    let classes = chars
+        .iter()
+        .map(|c| CharClass::of(*c))
+        .collect::<HashSet<_>>();
+    let size = classes
+        .iter()
+        .map(|class| {
+            let charset = policy.charset(*class);
+            // 不在字符集中的字符（如非 ascii 字符）至少按该字符集大小计算
+            charset.len().max(match class {
+                CharClass::Number => 10,
+                CharClass::Symbol => DEFAULT_SYMBOL_SPACE,
+                _ => 26,
+            })
+        })
+        .sum::<usize>();
+    size as f64
+}
+
+fn log10_factorial(n: usize) -> f64 {
+    (1..=n).map(|i| (i as f64).log10()).sum()
+}
+
+fn find_matches(chars: &[char], policy: &PasswordPolicy) -> Vec<PatternMatch> {
+    let mut matches = dictionary_matches(chars);
+    matches.extend(spatial_matches(chars));
+    matches.extend(sequence_matches(chars));
+    matches.extend(repeat_matches(chars, policy));
+    matches.extend(date_matches(chars));
+    matches
+}
+
+impl Dictionary {
+    fn new() -> Self {
+        let mut words = HashMap::new();
+        let english = ENGLISH_WORDS.lines().chain(
+            EFF_WORDLIST
+                .lines()
+                .filter_map(|line| line.split_whitespace().last()),
+        );
+        for word in english {
+            let rank = words.len() + 1;
+            words.entry(word).or_insert((rank, false));
+        }
+        // 常用密码优先
+        for (i, word) in COMMON_PASSWORDS.lines().enumerate() {
+            words.insert(word, (i + 1, true));
+        }
+        let max_len = words.keys().map(|w| w.chars().count()).max().unwrap_or(0);
+        Dictionary { words, max_len }
+    }
+}
+
+fn dictionary_matches(chars: &[char]) -> Vec<PatternMatch> {
+    let dict = &*DICTIONARY;
+    let lower = chars
+        .iter()
+        .map(|c| c.to_lowercase().next().unwrap_or(*c))
+        .collect::<Vec<_>>();
+    let mut matches = Vec::new();
+    for start in 0..chars.len() {
+        for end in start + 3..=chars.len().min(start + dict.max_len) {
+            let token = &lower[start..end];
+            let candidates = [
+                (token.iter().collect::<String>(), false, false),
+                (token.iter().rev().collect::<String>(), true, false),
+            ]
+            .into_iter()
+            .chain(unl33t(token).into_iter().map(|word| (word, false, true)));
+            let mut found: Option<(String, usize, bool, bool, bool)> = None;
+            for (word, reversed, l33t) in candidates {
+                if let Some((rank, common)) = dict.words.get(word.as_str()) {
+                    if found.as_ref().is_none_or(|f| *rank < f.1) {
+                        found = Some((word, *rank, *common, reversed, l33t));
+                    }
+                }
+            }
+            let Some((word, rank, common_password, reversed, l33t)) = found else {
+                continue;
+            };
+            let original = &chars[start..end];
+            let mut guesses = rank as f64 * uppercase_variations(original);
+            if reversed {
+                guesses *= 2.0;
+            }
+            if l33t {
+                guesses *= l33t_variations(token, &word);
+            }
+            matches.push(PatternMatch {
+                pattern: Pattern::Dictionary {
+                    word,
+                    rank,
+                    common_password,
+                    reversed,
+                    l33t,
+                },
+                token: original.iter().collect(),
+                start,
+                end,
+                guesses_log10: guesses.log10(),
+            });
+        }
+    }
+    matches
+}
+
+// 把 l33t 替换还原为字母；1 和 | 既可能是 i 也可能是 l，分别尝试
+fn unl33t(token: &[char]) -> Vec<String> {
+    if !token.iter().any(|c| l33t_letters(*c).is_some()) {
+        return Vec::new();
+    }
+    (0..2)
+        .map(|variant| {
+            token
+                .iter()
+                .map(|c| match l33t_letters(*c) {
+                    Some(letters) => letters
+                        .chars()
+                        .nth(variant)
+                        .or_else(|| letters.chars().next())
+                        .unwrap_or(*c),
+                    None => *c,
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn l33t_letters(c: char) -> Option<&'static str> {
+    L33T.iter().find(|(sub, _)| *sub == c).map(|(_, l)| *l)
+}
+
+// 全小写不增加猜测次数；首字母、末字母大写或全大写只翻倍；其他情况按大写字母的位置组合计算
+fn uppercase_variations(token: &[char]) -> f64 {
+    let upper = token.iter().filter(|c| c.is_uppercase()).count();
+    let lower = token.iter().filter(|c| c.is_lowercase()).count();
+    if upper == 0 {
+        return 1.0;
+    }
+    let first_only = token[0].is_uppercase() && upper == 1;
+    let last_only = token[token.len() - 1].is_uppercase() && upper == 1;
+    if lower == 0 || first_only || last_only {
+        return 2.0;
+    }
+    (1..=upper.min(lower))
+        .map(|i| n_choose_k(upper + lower, i))
+        .sum()
+}
+
+// 与 zxcvbn 相同，按每种替换分别计算：攻击者需要猜出该字母的哪几处被替换
+fn l33t_variations(token: &[char], word: &str) -> f64 {
+    let subs = token
+        .iter()
+        .zip(word.chars())
+        .filter(|(c, letter)| *c != letter)
+        .map(|(c, letter)| (*c, letter))
+        .collect::<HashSet<_>>();
+    subs.into_iter()
+        .map(|(sub, letter)| {
+            let subbed = token.iter().filter(|c| **c == sub).count();
+            let unsubbed = token.iter().filter(|c| **c == letter).count();
+            if unsubbed == 0 {
+                return 2.0;
+            }
+            (1..=subbed.min(unsubbed))
+                .map(|i| n_choose_k(subbed + unsubbed, i))
+                .sum()
+        })
+        .product()
+}
+
+fn n_choose_k(n: usize, k: usize) -> f64 {
+    (1..=k).fold(1.0, |acc, i| acc * (n + 1 - i) as f64 / i as f64)
+}
+
+// 按键在键盘上的 (行, 水平位置, 是否按下 shift)
+fn key_position(c: char) -> Option<(usize, f64, bool)> {
+    for (row, (plain, shifted)) in KEYBOARD.iter().zip(KEYBOARD_SHIFTED).enumerate() {
+        if let Some(col) = plain.chars().position(|k| k == c) {
+            return Some((row, col as f64 + KEYBOARD_OFFSETS[row], false));
+        }
+        if let Some(col) = shifted.chars().position(|k| k == c) {
+            return Some((row, col as f64 + KEYBOARD_OFFSETS[row], true));
+        }
+    }
+    None
+}
+
+// 相邻按键之间的方向，不相邻时返回 None
+fn key_direction(a: char, b: char) -> Option<(i32, i32)> {
+    let (row_a, x_a, _) = key_position(a)?;
+    let (row_b, x_b, _) = key_position(b)?;
+    let drow = row_b as i32 - row_a as i32;
+    let dx = x_b - x_a;
+    let adjacent = match drow {
+        0 => (dx.abs() - 1.0).abs() < 1e-9,
+        -1 | 1 => dx.abs() < 1.0,
+        _ => false,
+    };
+    adjacent.then_some((drow, dx.signum() as i32))
+}
+
+fn spatial_matches(chars: &[char]) -> Vec<PatternMatch> {
+    let mut matches = Vec::new();
+    let mut start = 0;
+    while start + 2 < chars.len() {
+        let mut end = start + 1;
+        let mut turns = 0;
+        let mut direction = None;
+        while end < chars.len() {
+            let Some(d) = key_direction(chars[end - 1], chars[end]) else {
+                break;
+            };
+            if direction != Some(d) {
+                turns += 1;
+                direction = Some(d);
+            }
+            end += 1;
+        }
+        if end - start >= 3 {
+            let token = &chars[start..end];
+            let shifted = token
+                .iter()
+                .filter(|c| key_position(**c).is_some_and(|(_, _, s)| s))
+                .count();
+            matches.push(PatternMatch {
+                pattern: Pattern::Spatial { turns, shifted },
+                token: token.iter().collect(),
+                start,
+                end,
+                guesses_log10: spatial_guesses(token.len(), turns, shifted).log10(),
+            });
+            start = end - 1;
+        } else {
+            start += 1;
+        }
+    }
+    matches
+}
+
+fn spatial_guesses(len: usize, turns: usize, shifted: usize) -> f64 {
+    let mut guesses = 0.0;
+    for i in 2..=len {
+        for j in 1..=turns.min(i - 1) {
+            guesses += n_choose_k(i - 1, j - 1) * KEYBOARD_STARTS * KEYBOARD_DEGREE.powi(j as i32);
+        }
+    }
+    let unshifted = len - shifted;
+    if shifted > 0 {
+        guesses *= if unshifted == 0 {
+            2.0
+        } else {
+            (1..=shifted.min(unshifted))
+                .map(|i| n_choose_k(len, i))
+                .sum()
+        };
+    }
+    guesses
+}
+
+// 同一类字符中间隔固定为 ±1 的连续序列，如 abc、9876
+fn sequence_matches(chars: &[char]) -> Vec<PatternMatch> {
+    let same_kind = |a: char, b: char| {
+        (a.is_ascii_lowercase() && b.is_ascii_lowercase())
+            || (a.is_ascii_uppercase() && b.is_ascii_uppercase())
+            || (a.is_ascii_digit() && b.is_ascii_digit())
+    };
+    let delta = |a: char, b: char| b as i32 - a as i32;
+    let mut matches = Vec::new();
+    let mut start = 0;
+    while start + 2 < chars.len() {
+        let d = delta(chars[start], chars[start + 1]);
+        let mut end = start + 1;
+        while end < chars.len()
+            && same_kind(chars[end - 1], chars[end])
+            && delta(chars[end - 1], chars[end]) == d
+            && d.abs() == 1
+        {
+            end += 1;
+        }
+        if end - start >= 3 {
+            let first = chars[start];
+            let base = if "aAzZ019".contains(first) {
+                4.0
+            } else if first.is_ascii_digit() {
+                10.0
+            } else {
+                26.0
+            };
+            let ascending = d > 0;
+            let factor = if ascending { 1.0 } else { 2.0 };
+            matches.push(PatternMatch {
+                pattern: Pattern::Sequence { ascending },
+                token: chars[start..end].iter().collect(),
+                start,
+                end,
+                guesses_log10: (base * factor * (end - start) as f64).log10(),
+            });
+            start = end - 1;
+        } else {
+            start += 1;
+        }
+    }
+    matches
+}
+
+// 同一片段重复多次，如 aaaa、abcabc；猜测次数为片段本身的猜测次数乘以重复次数
+fn repeat_matches(chars: &[char], policy: &PasswordPolicy) -> Vec<PatternMatch> {
+    let mut matches = Vec::new();
+    let mut start = 0;
+    while start + 1 < chars.len() {
+        let mut found: Option<(usize, usize)> = None;
+        for unit in 1..=(chars.len() - start) / 2 {
+            let pattern = &chars[start..start + unit];
+            let count = chars[start..]
+                .chunks(unit)
+                .take_while(|chunk| *chunk == pattern)
+                .count();
+            if count >= 2 && unit * count >= 3 && found.is_none_or(|(u, c)| unit * count > u * c) {
+                found = Some((unit, count));
+            }
+        }
+        let Some((unit, count)) = found else {
+            start += 1;
+            continue;
+        };
+        let end = start + unit * count;
+        let unit_chars = &chars[start..start + unit];
+        let (unit_guesses_log10, _) = estimate(unit_chars, policy);
+        matches.push(PatternMatch {
+            pattern: Pattern::Repeat {
+                unit: unit_chars.iter().collect(),
+                count,
+            },
+            token: chars[start..end].iter().collect(),
+            start,
+            end,
+            guesses_log10: unit_guesses_log10 + (count as f64).log10(),
+        });
+        start = end;
+    }
+    matches
+}
+
+fn date_matches(chars: &[char]) -> Vec<PatternMatch> {
+    let this_year = Local::now().year();
+    let year_space = |year: i32| ((year - this_year).abs() as f64).max(MIN_YEAR_SPACE);
+    let mut matches = Vec::new();
+    for start in 0..chars.len() {
+        for end in start + 4..=chars.len().min(start + 10) {
+            let token = chars[start..end].iter().collect::<String>();
+            let guesses = if token.chars().all(|c| c.is_ascii_digit()) {
+                let year = (end - start == 4)
+                    .then(|| token.parse::<i32>().ok())
+                    .flatten()
+                    .filter(|y| (1900..=2049).contains(y));
+                // 四位数字同时可以看作年份时按年份计算，猜测次数更少
+                match (year, parse_date_digits(&token)) {
+                    (Some(year), _) => Some((year, year_space(year))),
+                    (None, Some(year)) => Some((year, 365.0 * year_space(year))),
+                    _ => None,
+                }
+            } else {
+                // 带分隔符时两个分隔符必须相同
+                parse_date_separated(&token).map(|year| (year, 365.0 * year_space(year) * 4.0))
+            };
+            if let Some((year, guesses)) = guesses {
+                matches.push(PatternMatch {
+                    pattern: Pattern::Date { year },
+                    token,
+                    start,
+                    end,
+                    guesses_log10: guesses.log10(),
+                });
+            }
+        }
+    }
+    matches
+}
+
+// 不带分隔符的日期，按长度尝试不同的拆分方式
+fn parse_date_digits(token: &str) -> Option<i32> {
+    let splits: &[(usize, usize)] = match token.len() {
+        4 => &[(1, 2), (2, 3)],
+        5 => &[(1, 3), (2, 3)],
+        6 => &[(1, 2), (2, 4), (4, 5)],
+        7 => &[(1, 3), (2, 3), (4, 5), (4, 6)],
+        8 => &[(2, 4), (4, 6)],
+        _ => return None,
+    };
+    splits
+        .iter()
+        .find_map(|(k, l)| parse_date_parts(&[&token[..*k], &token[*k..*l], &token[*l..]]))
+}
+
+fn parse_date_separated(token: &str) -> Option<i32> {
+    let sep = token.chars().find(|c| !c.is_ascii_digit())?;
+    if !SEPARATORS.contains(sep) {
+        return None;
+    }
+    let parts = token.split(sep).collect::<Vec<_>>();
+    if parts.len() != 3
+        || parts
+            .iter()
+            .any(|p| p.is_empty() || !p.chars().all(|c| c.is_ascii_digit()))
+    {
+        return None;
+    }
+    parse_date_parts(&[parts[0], parts[1], parts[2]])
+}
+
+// 年份在首或尾，其余两段为月和日（顺序任意），返回年份
+fn parse_date_parts(parts: &[&str; 3]) -> Option<i32> {
+    let day_month = |a: &str, b: &str| {
+        let (a, b) = (a.parse::<u32>().ok()?, b.parse::<u32>().ok()?);
+        let valid = |d: u32, m: u32| (1..=31).contains(&d) && (1..=12).contains(&m);
+        (valid(a, b) || valid(b, a)).then_some(())
+    };
+    [
+        (parts[2], parts[0], parts[1]),
+        (parts[0], parts[1], parts[2]),
+    ]
+    .into_iter()
+    .find_map(|(year, a, b)| {
+        if a.len() > 2 || b.len() > 2 {
+            return None;
+        }
+        let year = parse_year(year)?;
+        day_month(a, b).map(|_| year)
+    })
+}
+
+fn parse_year(year: &str) -> Option<i32> {
+    let value = year.parse::<i32>().ok()?;
+    match year.len() {
+        2 if value < 50 => Some(2000 + value),
+        2 => Some(1900 + value),
+        4 if (1000..=2050).contains(&value) => Some(value),
+        _ => None,
+    }
+}
+
+fn feedback(score: u8, matches: &[PatternMatch], len: usize) -> (Option<String>, Vec<String>) {
+    if len == 0 {
+        return (
+            None,
+            vec![
+                "Use a few words, avoid common phrases".to_string(),
+                "No need for symbols, digits, or uppercase letters".to_string(),
+            ],
+        );
+    }
+    if score > 2 {
+        return (None, Vec::new());
+    }
+    let mut suggestions = vec!["Add another word or two. Uncommon words are better.".to_string()];
+    let longest = matches
+        .iter()
+        .filter(|m| m.pattern != Pattern::Bruteforce)
+        .max_by_key(|m| m.end - m.start);
+    let Some(m) = longest else {
+        return (None, suggestions);
+    };
+    let whole = matches.len() == 1;
+    let warning = match &m.pattern {
+        Pattern::Dictionary {
+            rank,
+            common_password,
+            reversed,
+            l33t,
+            ..
+        } => {
+            let chars = m.token.chars().collect::<Vec<_>>();
+            let upper = chars.iter().filter(|c| c.is_uppercase()).count();
+            if upper == chars.len() {
+                suggestions
+                    .push("All-uppercase is almost as easy to guess as all-lowercase".into());
+            } else if upper == 1 && chars[0].is_uppercase() {
+                suggestions.push("Capitalization doesn't help very much".into());
+            }
+            if *reversed && chars.len() >= 4 {
+                suggestions.push("Reversed words aren't much harder to guess".into());
+            }
+            if *l33t {
+                suggestions.push(
+                    "Predictable substitutions like '@' instead of 'a' don't help very much".into(),
+                );
+            }
+            match (*common_password, whole) {
+                (true, true) if *rank <= 10 => Some("This is a top-10 common password"),
+                (true, true) if *rank <= 100 => Some("This is a top-100 common password"),
+                (true, true) => Some("This is a very common password"),
+                (true, false) => Some("This is similar to a commonly used password"),
+                (false, true) => Some("A word by itself is easy to guess"),
+                (false, false) => None,
+            }
+        }
+        Pattern::Spatial { turns, .. } => {
+            suggestions.push("Use a longer keyboard pattern with more turns".into());
+            if *turns == 1 {
+                Some("Straight rows of keys are easy to guess")
+            } else {
+                Some("Short keyboard patterns are easy to guess")
+            }
+        }
+        Pattern::Sequence { .. } => {
+            suggestions.push("Avoid sequences".into());
+            Some("Sequences like abc or 6543 are easy to guess")
+        }
+        Pattern::Repeat { unit, .. } => {
+            suggestions.push("Avoid repeated words and characters".into());
+            if unit.chars().count() == 1 {
+                Some("Repeats like \"aaa\" are easy to guess")
+            } else {
+                Some("Repeats like \"abcabcabc\" are only slightly harder to guess than \"abc\"")
+            }
+        }
+        Pattern::Date { .. } => {
+            suggestions.push("Avoid dates and years that are associated with you".into());
+            Some("Dates are often easy to guess")
+        }
+        Pattern::Bruteforce => None,
+    };
+    (warning.map(String::from), suggestions)
+}
+
+// 按科学计数法显示 10 的 log10 次方
+fn display_guesses(log10: f64) -> String {
+    let mut exponent = log10.floor();
+    let mut mantissa = 10f64.powf(log10 - exponent);
+    if mantissa >= 9.9995 {
+        mantissa /= 10.0;
+        exponent += 1.0;
+    }
+    format!("{:.3}e{}", mantissa, exponent)
+}
+
+fn display_time(seconds: f64) -> String {
+    const UNITS: [(f64, &str); 6] = [
+        (60.0, "second"),
+        (60.0, "minute"),
+        (24.0, "hour"),
+        (31.0, "day"),
+        (12.0, "month"),
+        (100.0, "year"),
+    ];
+    if seconds < 1.0 {
+        return "less than a second".to_string();
+    }
+    let mut value = seconds;
+    for (size, unit) in UNITS {
+        if value < size {
+            let n = value.round() as u64;
+            return format!("{} {}{}", n, unit, if n == 1 { "" } else { "s" });
+        }
+        value /= size;
+    }
+    "centuries".to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn check(password: &str) -> StrengthReport {
+        process_pass_strength(password, &PasswordPolicy::default())
+    }
+
+    fn patterns(password: &str) -> Vec<(&'static str, String)> {
+        check(password)
+            .matches
+            .into_iter()
+            .map(|m| (m.pattern.name(), m.token))
+            .collect()
+    }
+
+    #[test]
+    fn test_pattern_matching() {
+        assert_eq!(
+            patterns("password"),
+            vec![("dictionary", "password".into())]
+        );
+        assert_eq!(
+            patterns("qwertyuiop"),
+            vec![("dictionary", "qwertyuiop".into())]
+        );
+        assert_eq!(patterns("zxcvfr"), vec![("spatial", "zxcvfr".into())]);
+        assert_eq!(patterns("lmnopq"), vec![("sequence", "lmnopq".into())]);
+        assert_eq!(patterns("xyzxyzxyz"), vec![("repeat", "xyzxyzxyz".into())]);
+        assert_eq!(
+            patterns("Tr0ub4dor&1987"),
+            vec![
+                ("dictionary", "Tr0ub4dor".into()),
+                ("bruteforce", "&".into()),
+                ("date", "1987".into())
+            ]
+        );
+        assert_eq!(
+            patterns("P@ssw0rd13.05.1990"),
+            vec![
+                ("dictionary", "P@ssw0rd".into()),
+                ("date", "13.05.1990".into())
+            ]
+        );
+        let m = &check("drowssap").matches[0];
+        assert!(matches!(
+            m.pattern,
+            Pattern::Dictionary { reversed: true, .. }
+        ));
+        assert!(parse_date_digits("310599").is_some());
+        assert!(parse_date_digits("319999").is_none());
+    }
+
+    #[test]
+    fn test_score() {
+        let weak = check("password");
+        assert_eq!(weak.score, 0);
+        assert_eq!(
+            weak.warning.as_deref(),
+            Some("This is a top-10 common password")
+        );
+        assert_eq!(check("").score, 0);
+        assert!(check("P@ssw0rd").guesses_log10 < 5.0);
+        assert!(check("abcabcabcabc").score <= 1);
+        assert_eq!(check("correct horse battery staple").score, 4);
+        // 常见的弱密码不能因为混用大小写、数字和符号而被评为强密码
+        let report = check("Tr0ub4dor&3");
+        assert!(report.score <= 3 && report.guesses_log10 < 9.0);
+        assert_eq!(check("Elephant2023").score, 1);
+        assert_eq!(check("january1990").score, 1);
+        assert_eq!(l33t_variations(&['p', '4', 's', 's'], "pass"), 2.0);
+        assert_eq!(l33t_variations(&['p', '4', 'p', 'a'], "papa"), 2.0);
+        // genpass 按默认策略生成的密码应被评为最强
+        for password in crate::process_gen_pass(&PasswordPolicy::default(), 5).unwrap() {
+            let report = check(&password);
+            assert_eq!(report.score, 4, "{}", password);
+            assert!(report.violations.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_long_password() {
+        // 超出部分不计入，重复的长密码仍然是弱密码
+        for password in ["a".repeat(150), "password".repeat(20)] {
+            let report = check(&password);
+            assert!(report.score <= 1, "{}", report.render(false));
+            assert!(report.matches.iter().all(|m| m.end <= MAX_MATCH_LENGTH));
+        }
+        let report =
+            check(&crate::process_gen_pass(&PasswordPolicy::default(), 1).unwrap()[0].repeat(20));
+        assert!(report.guesses_log10.is_finite());
+        let text = report.render(false);
+        assert!(!text.contains("inf") && !text.contains("NaN"), "{}", text);
+        assert_eq!(display_guesses(2.0), "1.000e2");
+        assert_eq!(display_guesses(600.0f64.log10() + 300.0), "6.000e302");
+    }
+
+    #[test]
+    fn test_display_time() {
+        assert_eq!(display_time(0.5), "less than a second");
+        assert_eq!(display_time(1.0), "1 second");
+        assert_eq!(display_time(7200.0), "2 hours");
+        assert_eq!(display_time(86400.0 * 400.0), "1 year");
+        assert_eq!(display_time(1e12), "centuries");
+    }
+}
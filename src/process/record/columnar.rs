@@ -0,0 +1,428 @@
+use super::infer::{is_timestamp, ColumnType};
+use super::writer::{record_headers, record_row};
+use super::RecordWrite;
+use crate::cli::ParquetCompression;
+use anyhow::Result;
+use arrow::array::{
+    ArrayRef, BooleanArray, Date32Array, Float64Array, Int64Array, StringArray,
+    TimestampMicrosecondArray,
+};
+use arrow::datatypes::{DataType, Field, Schema, SchemaRef, TimeUnit};
+use arrow::ipc::writer::FileWriter;
+use arrow::record_batch::RecordBatch;
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::{Compression, GzipLevel, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use serde_json::Value;
+use std::io::Write;
+use std::sync::Arc;
+
+const BATCH_ROWS: usize = 8192;
+
+#[derive(Debug, Clone, Copy)]
+pub enum ColumnarFormat {
+    Parquet(Compression),
+    Arrow,
+}
+
+// 两种格式都先写入内存缓冲区，每写完一批就转存到输出，避免要求输出实现 Send
+enum ColumnarSink {
+    Parquet(ArrowWriter<Vec<u8>>),
+    Arrow(FileWriter<Vec<u8>>),
+}
+
+// 列类型由第一批记录的值决定，之后与之不符的值写为 null，结束时在 stderr 报告
+pub struct ColumnarWriter<'a> {
+    writer: Box<dyn Write + 'a>,
+    format: ColumnarFormat,
+    sink: Option<(ColumnarSink, SchemaRef)>,
+    headers: Option<Vec<String>>,
+    rows: Vec<Vec<Value>>,
+    mismatched: Vec<usize>,
+}
+
+impl<'a> ColumnarWriter<'a> {
+    pub fn new(writer: Box<dyn Write + 'a>, format: ColumnarFormat) -> Self {
+        ColumnarWriter {
+            writer,
+            format,
+            sink: None,
+            headers: None,
+            rows: Vec::new(),
+            mismatched: Vec::new(),
+        }
+    }
+
+    fn open_sink(&self, schema: Schema) -> Result<(ColumnarSink, SchemaRef)> {
+        let schema = Arc::new(schema);
+        let sink = match self.format {
+            ColumnarFormat::Parquet(compression) => {
+                let props = WriterProperties::builder()
+                    .set_compression(compression)
+                    .build();
+                let writer = ArrowWriter::try_new(Vec::new(), schema.clone(), Some(props))?;
+                ColumnarSink::Parquet(writer)
+            }
+            ColumnarFormat::Arrow => ColumnarSink::Arrow(FileWriter::try_new(Vec::new(), &schema)?),
+        };
+        Ok((sink, schema))
+    }
+
+    fn write_batch(&mut self) -> Result<()> {
+        if self.rows.is_empty() {
+            return Ok(());
+        }
+        let headers = self.headers.as_deref().unwrap_or_default();
+        let (mut sink, schema) = match self.sink.take() {
+            Some(sink) => sink,
+            None => self.open_sink(infer_schema(headers, &self.rows))?,
+        };
+        let (batch, mismatched) = build_batch(schema.clone(), &self.rows)?;
+        self.rows.clear();
+        self.mismatched.resize(mismatched.len(), 0);
+        for (total, count) in self.mismatched.iter_mut().zip(mismatched) {
+            *total += count;
+        }
+        sink.write(&batch)?;
+        self.writer.write_all(&std::mem::take(sink.buffer()))?;
+        self.sink = Some((sink, schema));
+        Ok(())
+    }
+}
+
+impl ColumnarSink {
+    fn write(&mut self, batch: &RecordBatch) -> Result<()> {
+        match self {
+            ColumnarSink::Parquet(writer) => {
+                writer.write(batch)?;
+                writer.flush()?;
+            }
+            ColumnarSink::Arrow(writer) => writer.write(batch)?,
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        match self {
+            ColumnarSink::Parquet(writer) => {
+                writer.finish()?;
+            }
+            ColumnarSink::Arrow(writer) => writer.finish()?,
+        }
+        Ok(())
+    }
+
+    fn buffer(&mut self) -> &mut Vec<u8> {
+        match self {
+            ColumnarSink::Parquet(writer) => writer.inner_mut(),
+            ColumnarSink::Arrow(writer) => writer.get_mut(),
+        }
+    }
+}
+
+impl From<ParquetCompression> for Compression {
+    fn from(compression: ParquetCompression) -> Self {
+        match compression {
+            ParquetCompression::None => Compression::UNCOMPRESSED,
+            ParquetCompression::Snappy => Compression::SNAPPY,
+            ParquetCompression::Gzip => Compression::GZIP(GzipLevel::default()),
+            ParquetCompression::Zstd => Compression::ZSTD(ZstdLevel::default()),
+            ParquetCompression::Lz4 => Compression::LZ4_RAW,
+        }
+    }
+}
+
+impl RecordWrite for ColumnarWriter<'_> {
+    fn write_record(&mut self, record: &Value) -> Result<()> {
+        let headers = self.headers.get_or_insert_with(|| record_headers(record));
+        let row = record_row(headers, record)?;
+        self.rows.push(row);
+        if self.rows.len() >= BATCH_ROWS {
+            self.write_batch()?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.write_batch()?;
+        // 没有任何记录时输出一个空 schema 的文件
+        let (mut sink, schema) = match self.sink.take() {
+            Some(sink) => sink,
+            None => self.open_sink(Schema::empty())?,
+        };
+        sink.finish()?;
+        self.writer.write_all(sink.buffer())?;
+        self.writer.flush()?;
+        for (field, count) in schema.fields().iter().zip(&self.mismatched) {
+            if *count > 0 {
+                eprintln!(
+                    "Column {:?}: {} value(s) did not match type {} and were written as null, try a larger --sample or --types",
+                    field.name(),
+                    count,
+                    field.data_type()
+                );
+            }
+        }
+        Ok(())
+    }
+}
+
+fn value_type(value: &Value) -> ColumnType {
+    match value {
+        Value::Null => ColumnType::Null,
+        Value::Bool(_) => ColumnType::Bool,
+        Value::Number(v) if v.is_i64() => ColumnType::Integer,
+        Value::Number(_) => ColumnType::Float,
+        Value::String(v) if ColumnType::detect(v) == ColumnType::Date => ColumnType::Date,
+        _ => ColumnType::String,
+    }
+}
+
+fn infer_schema(headers: &[String], rows: &[Vec<Value>]) -> Schema {
+    let fields = headers
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let column_type = rows
+                .iter()
+                .map(|row| value_type(&row[i]))
+                .fold(ColumnType::Null, ColumnType::merge);
+            let data_type = match column_type {
+                ColumnType::Bool => DataType::Boolean,
+                ColumnType::Integer => DataType::Int64,
+                ColumnType::Float => DataType::Float64,
+                // 只有日期时用 Date32，出现带时间的值时统一转为 UTC 时间戳
+                ColumnType::Date
+                    if rows
+                        .iter()
+                        .any(|row| row[i].as_str().is_some_and(is_timestamp)) =>
+                {
+                    DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into()))
+                }
+                ColumnType::Date => DataType::Date32,
+                ColumnType::Null | ColumnType::String => DataType::Utf8,
+            };
+            Field::new(name, data_type, true)
+        })
+        .collect::<Vec<_>>();
+    Schema::new(fields)
+}
+
+// 返回的计数为每列与类型不符、被写为 null 的值的个数
+fn build_batch(schema: SchemaRef, rows: &[Vec<Value>]) -> Result<(RecordBatch, Vec<usize>)> {
+    let mut mismatched = vec![0; schema.fields().len()];
+    let columns = schema
+        .fields()
+        .iter()
+        .zip(mismatched.iter_mut())
+        .enumerate()
+        .map(|(i, (field, count))| build_column(field, rows.iter().map(|row| &row[i]), count))
+        .collect::<Vec<_>>();
+    Ok((RecordBatch::try_new(schema, columns)?, mismatched))
+}
+
+fn build_column<'v>(
+    field: &Field,
+    values: impl Iterator<Item = &'v Value>,
+    mismatched: &mut usize,
+) -> ArrayRef {
+    match field.data_type() {
+        DataType::Boolean => Arc::new(BooleanArray::from(typed_values(
+            values,
+            Value::as_bool,
+            mismatched,
+        ))),
+        DataType::Int64 => Arc::new(Int64Array::from(typed_values(
+            values,
+            Value::as_i64,
+            mismatched,
+        ))),
+        DataType::Float64 => Arc::new(Float64Array::from(typed_values(
+            values,
+            Value::as_f64,
+            mismatched,
+        ))),
+        DataType::Date32 => Arc::new(Date32Array::from(typed_values(
+            values,
+            |v| v.as_str().and_then(date_days),
+            mismatched,
+        ))),
+        DataType::Timestamp(_, tz) => Arc::new(
+            TimestampMicrosecondArray::from(typed_values(
+                values,
+                |v| v.as_str().and_then(timestamp_micros),
+                mismatched,
+            ))
+            .with_timezone_opt(tz.clone()),
+        ),
+        _ => Arc::new(
+            values
+                .map(|v| match v {
+                    Value::Null => None,
+                    Value::String(s) => Some(s.clone()),
+                    v => Some(v.to_string()),
+                })
+                .collect::<StringArray>(),
+        ),
+    }
+}
+
+// 空值为 null；其余值转换失败说明与推断的类型不符，同样写为 null 并计数，
+// 不能因为第一批之后的某个值中断输出，留下不完整的文件
+fn typed_values<'v, T>(
+    values: impl Iterator<Item = &'v Value>,
+    convert: impl Fn(&Value) -> Option<T>,
+    mismatched: &mut usize,
+) -> Vec<Option<T>> {
+    values
+        .map(|value| match value {
+            Value::Null => None,
+            v => {
+                let converted = convert(v);
+                if converted.is_none() {
+                    *mismatched += 1;
+                }
+                converted
+            }
+        })
+        .collect()
+}
+
+fn date_days(value: &str) -> Option<i32> {
+    let date = NaiveDate::parse_from_str(value.trim(), "%Y-%m-%d").ok()?;
+    Some((date - NaiveDate::default()).num_days() as i32)
+}
+
+// 不带时区的时间和纯日期按 UTC 处理，纯日期取零点
+fn timestamp_micros(value: &str) -> Option<i64> {
+    let value = value.trim();
+    if let Ok(dt) = DateTime::parse_from_rfc3339(value) {
+        return Some(dt.timestamp_micros());
+    }
+    ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+        .or_else(|| {
+            NaiveDate::parse_from_str(value, "%Y-%m-%d")
+                .ok()?
+                .and_hms_opt(0, 0, 0)
+        })
+        .map(|dt| dt.and_utc().timestamp_micros())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use arrow::array::Array;
+    use arrow::ipc::reader::FileReader;
+    use bytes::Bytes;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+    use serde_json::json;
+    use std::io::Cursor;
+
+    fn records() -> Vec<Value> {
+        vec![
+            json!({"id": 1, "name": "a", "score": 1.5, "ok": true, "day": "2024-01-02"}),
+            json!({"id": 2, "name": null, "score": 2, "ok": null, "day": "2024-01-03"}),
+        ]
+    }
+
+    fn write(format: ColumnarFormat, records: &[Value]) -> Result<Vec<u8>> {
+        let mut buf = Vec::new();
+        let mut writer = ColumnarWriter::new(Box::new(&mut buf), format);
+        for record in records {
+            writer.write_record(record)?;
+        }
+        writer.finish()?;
+        drop(writer);
+        Ok(buf)
+    }
+
+    #[test]
+    fn test_parquet_writer() {
+        let buf = write(ColumnarFormat::Parquet(Compression::SNAPPY), &records()).unwrap();
+        let reader = ParquetRecordBatchReaderBuilder::try_new(Bytes::from(buf))
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+        let schema = batches[0].schema();
+        let types = schema
+            .fields()
+            .iter()
+            .map(|f| f.data_type().clone())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            types,
+            vec![
+                DataType::Int64,
+                DataType::Utf8,
+                DataType::Float64,
+                DataType::Boolean,
+                DataType::Date32
+            ]
+        );
+        assert_eq!(batches[0].num_rows(), 2);
+        assert_eq!(batches[0].column(1).null_count(), 1);
+    }
+
+    #[test]
+    fn test_arrow_writer() {
+        let buf = write(ColumnarFormat::Arrow, &records()).unwrap();
+        let reader = FileReader::try_new(Cursor::new(buf), None).unwrap();
+        let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+        let ids = batches[0]
+            .column(0)
+            .as_any()
+            .downcast_ref::<Int64Array>()
+            .unwrap();
+        assert_eq!(ids.values(), &[1, 2]);
+    }
+
+    #[test]
+    fn test_build_batch_type_mismatch() {
+        let headers = vec!["id".to_string()];
+        let schema = Arc::new(infer_schema(&headers, &[vec![json!(1)]]));
+        let (batch, mismatched) =
+            build_batch(schema.clone(), &[vec![json!(2)], vec![Value::Null]]).unwrap();
+        assert_eq!((batch.column(0).null_count(), mismatched), (1, vec![0]));
+        let (batch, mismatched) = build_batch(schema, &[vec![json!("x")]]).unwrap();
+        assert_eq!((batch.column(0).null_count(), mismatched), (1, vec![1]));
+    }
+
+    #[test]
+    fn test_mismatch_after_first_batch() {
+        // 第一批之后出现与类型不符的值时仍然输出完整可读的文件
+        let mut records = (0..BATCH_ROWS as i64 + 10)
+            .map(|i| json!({ "id": i }))
+            .collect::<Vec<_>>();
+        records.push(json!({"id": "n/a"}));
+        let buf = write(ColumnarFormat::Parquet(Compression::SNAPPY), &records).unwrap();
+        let reader = ParquetRecordBatchReaderBuilder::try_new(Bytes::from(buf))
+            .unwrap()
+            .build()
+            .unwrap();
+        let batches = reader.collect::<Result<Vec<_>, _>>().unwrap();
+        let rows = batches.iter().map(|b| b.num_rows()).sum::<usize>();
+        let nulls = batches
+            .iter()
+            .map(|b| b.column(0).null_count())
+            .sum::<usize>();
+        assert_eq!((rows, nulls), (records.len(), 1));
+        assert_eq!(batches[0].schema().field(0).data_type(), &DataType::Int64);
+    }
+
+    #[test]
+    fn test_timestamp_micros() {
+        assert_eq!(timestamp_micros("1970-01-01T00:00:01Z"), Some(1_000_000));
+        assert_eq!(timestamp_micros("1970-01-02"), Some(86_400_000_000));
+        assert_eq!(date_days("1970-01-11"), Some(10));
+        assert_eq!(timestamp_micros("1970-01-01 00:00:02"), Some(2_000_000));
+        let schema = infer_schema(&["t".to_string()], &[vec![json!("2024-01-02 10:00:00")]]);
+        assert!(matches!(
+            schema.field(0).data_type(),
+            DataType::Timestamp(TimeUnit::Microsecond, _)
+        ));
+    }
+}
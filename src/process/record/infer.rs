@@ -0,0 +1,262 @@
+use crate::cli::CsvInferOpts;
+use anyhow::Result;
+use chrono::{DateTime, NaiveDate, NaiveDateTime};
+use csv::{Reader, StringRecord};
+use serde::{Deserialize, Serialize};
+use serde_json::{Number, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::io::Read;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ColumnType {
+    Null,
+    Bool,
+    Integer,
+    Float,
+    Date,
+    String,
+}
+
+pub struct ColumnTypes {
+    types: Vec<ColumnType>,
+}
+
+// 带时间部分的日期时间值，纯日期不算
+pub fn is_timestamp(cell: &str) -> bool {
+    let cell = cell.trim();
+    DateTime::parse_from_rfc3339(cell).is_ok()
+        || NaiveDateTime::parse_from_str(cell, "%Y-%m-%dT%H:%M:%S%.f").is_ok()
+        || NaiveDateTime::parse_from_str(cell, "%Y-%m-%d %H:%M:%S%.f").is_ok()
+}
+
+// 整数部分以 0 开头且不只是 0，如 007、-01、00.5
+fn has_leading_zero(cell: &str) -> bool {
+    let digits = cell.strip_prefix(['+', '-']).unwrap_or(cell).as_bytes();
+    digits.len() > 1 && digits[0] == b'0' && digits[1].is_ascii_digit()
+}
+
+impl ColumnType {
+    pub fn detect(cell: &str) -> Self {
+        let cell = cell.trim();
+        if cell.is_empty() {
+            ColumnType::Null
+        } else if cell.eq_ignore_ascii_case("true") || cell.eq_ignore_ascii_case("false") {
+            ColumnType::Bool
+        } else if has_leading_zero(cell) {
+            // 编号、邮编等带前导零的值按字符串保留，转成数字会丢掉前导零
+            ColumnType::String
+        } else if cell.parse::<i64>().is_ok() {
+            ColumnType::Integer
+        } else if cell.parse::<f64>().is_ok_and(|v| v.is_finite()) {
+            ColumnType::Float
+        } else if is_timestamp(cell) || NaiveDate::parse_from_str(cell, "%Y-%m-%d").is_ok() {
+            ColumnType::Date
+        } else {
+            ColumnType::String
+        }
+    }
+
+    // 合并两种类型，得到能同时容纳两者的最窄类型
+    pub fn merge(self, other: Self) -> Self {
+        match (self, other) {
+            (a, b) if a == b => a,
+            (ColumnType::Null, v) | (v, ColumnType::Null) => v,
+            (ColumnType::Integer, ColumnType::Float) | (ColumnType::Float, ColumnType::Integer) => {
+                ColumnType::Float
+            }
+            _ => ColumnType::String,
+        }
+    }
+
+    pub fn to_value(self, cell: &str) -> Value {
+        if self == ColumnType::String {
+            return Value::String(cell.to_string());
+        }
+        let trimmed = cell.trim();
+        if trimmed.is_empty() {
+            return Value::Null;
+        }
+        let value = match self {
+            ColumnType::Bool => trimmed.to_lowercase().parse::<bool>().ok().map(Value::Bool),
+            ColumnType::Integer if !has_leading_zero(trimmed) => {
+                trimmed.parse::<i64>().ok().map(Value::from)
+            }
+            ColumnType::Float => trimmed
+                .parse::<f64>()
+                .ok()
+                .and_then(Number::from_f64)
+                .map(Value::Number),
+            ColumnType::Date if ColumnType::detect(trimmed) == ColumnType::Date => {
+                Some(Value::String(trimmed.to_string()))
+            }
+            _ => None,
+        };
+        // 与推断类型不符的单元格按原始字符串输出
+        value.unwrap_or_else(|| Value::String(cell.to_string()))
+    }
+}
+
+impl ColumnTypes {
+    pub fn untyped(len: usize) -> Self {
+        ColumnTypes {
+            types: vec![ColumnType::String; len],
+        }
+    }
+
+    pub fn infer(
+        headers: &StringRecord,
+        samples: &[StringRecord],
+        overrides: &HashMap<String, ColumnType>,
+    ) -> Self {
+        let types = headers
+            .iter()
+            .enumerate()
+            .map(|(i, name)| {
+                if let Some(t) = overrides.get(name) {
+                    return *t;
+                }
+                samples
+                    .iter()
+                    .filter_map(|record| record.get(i))
+                    .map(ColumnType::detect)
+                    .fold(ColumnType::Null, ColumnType::merge)
+            })
+            .collect();
+        ColumnTypes { types }
+    }
+
+    #[cfg(test)]
+    pub fn types(&self) -> &[ColumnType] {
+        &self.types
+    }
+
+    pub fn to_value(&self, headers: &StringRecord, record: &StringRecord) -> Value {
+        headers
+            .iter()
+            .zip(record.iter())
+            .enumerate()
+            .map(|(i, (name, cell))| {
+                let column_type = self.types.get(i).copied().unwrap_or(ColumnType::String);
+                (name.to_string(), column_type.to_value(cell))
+            })
+            .collect()
+    }
+}
+
+pub fn load_type_overrides(path: &str) -> Result<HashMap<String, ColumnType>> {
+    super::load_document(path)
+}
+
+// 未开启推断时所有列按字符串处理；开启时先缓存 sample 条记录用于推断，调用方需先输出这些记录
+pub fn sample_column_types(
+    reader: &mut Reader<impl Read>,
+    headers: &StringRecord,
+    opts: &CsvInferOpts,
+) -> Result<(ColumnTypes, Vec<StringRecord>)> {
+    if !opts.infer && opts.types.is_none() {
+        return Ok((ColumnTypes::untyped(headers.len()), Vec::new()));
+    }
+    let overrides = match &opts.types {
+        Some(path) => load_type_overrides(path)?,
+        None => HashMap::new(),
+    };
+    let mut samples = Vec::new();
+    let mut record = StringRecord::new();
+    while samples.len() < opts.sample && reader.read_record(&mut record)? {
+        samples.push(record.clone());
+    }
+    let types = ColumnTypes::infer(headers, &samples, &overrides);
+    Ok((types, samples))
+}
+
+impl fmt::Display for ColumnType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColumnType::Null => write!(f, "null"),
+            ColumnType::Bool => write!(f, "bool"),
+            ColumnType::Integer => write!(f, "integer"),
+            ColumnType::Float => write!(f, "float"),
+            ColumnType::Date => write!(f, "date"),
+            ColumnType::String => write!(f, "string"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_column_type_detect() {
+        assert_eq!(ColumnType::detect(""), ColumnType::Null);
+        assert_eq!(ColumnType::detect("TRUE"), ColumnType::Bool);
+        assert_eq!(ColumnType::detect("-42"), ColumnType::Integer);
+        assert_eq!(ColumnType::detect("3.14"), ColumnType::Float);
+        assert_eq!(ColumnType::detect("0"), ColumnType::Integer);
+        assert_eq!(ColumnType::detect("-0"), ColumnType::Integer);
+        assert_eq!(ColumnType::detect("0.5"), ColumnType::Float);
+        assert_eq!(ColumnType::detect("01234"), ColumnType::String);
+        assert_eq!(ColumnType::detect("-007"), ColumnType::String);
+        assert_eq!(ColumnType::detect("00.5"), ColumnType::String);
+        assert_eq!(ColumnType::detect("NaN"), ColumnType::String);
+        assert_eq!(ColumnType::detect("2024-05-01"), ColumnType::Date);
+        assert_eq!(
+            ColumnType::detect("2024-05-01T10:00:00+08:00"),
+            ColumnType::Date
+        );
+        assert_eq!(ColumnType::detect("2024-05-01 10:00:00"), ColumnType::Date);
+        assert_eq!(ColumnType::detect("Apr 18, 1990"), ColumnType::String);
+        assert!(is_timestamp("2024-05-01T10:00:00+08:00"));
+        assert!(is_timestamp("2024-05-01 10:00:00.5"));
+        assert!(!is_timestamp(" 2024-05-01    "));
+        assert!(!is_timestamp("2024-05-01 noon"));
+    }
+
+    #[test]
+    fn test_column_type_merge() {
+        assert_eq!(
+            ColumnType::Null.merge(ColumnType::Integer),
+            ColumnType::Integer
+        );
+        assert_eq!(
+            ColumnType::Integer.merge(ColumnType::Float),
+            ColumnType::Float
+        );
+        assert_eq!(
+            ColumnType::Bool.merge(ColumnType::Integer),
+            ColumnType::String
+        );
+    }
+
+    #[test]
+    fn test_column_types_infer() {
+        let headers = StringRecord::from(vec!["id", "score", "ok", "note", "zip"]);
+        let samples = vec![
+            StringRecord::from(vec!["1", "1.5", "true", "", "01234"]),
+            StringRecord::from(vec!["2", "2", "false", "", "98765"]),
+        ];
+        let overrides = HashMap::from([("zip".to_string(), ColumnType::String)]);
+        let types = ColumnTypes::infer(&headers, &samples, &overrides);
+        assert_eq!(
+            types.types(),
+            &[
+                ColumnType::Integer,
+                ColumnType::Float,
+                ColumnType::Bool,
+                ColumnType::Null,
+                ColumnType::String
+            ]
+        );
+        let value = types.to_value(&headers, &samples[0]);
+        let inferred = ColumnTypes::infer(&headers, &samples, &HashMap::new());
+        assert_eq!(inferred.types()[4], ColumnType::String);
+        assert_eq!(
+            value,
+            json!({"id": 1, "score": 1.5, "ok": true, "note": null, "zip": "01234"})
+        );
+        assert_eq!(ColumnType::Integer.to_value("007"), json!("007"));
+    }
+}
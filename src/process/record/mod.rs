@@ -0,0 +1,103 @@
+use super::data_to_output;
+use crate::cli::{CsvWriteOpts, OutputFormat};
+use anyhow::{bail, Result};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::io::Write;
+use std::path::Path;
+
+mod columnar;
+pub mod infer;
+mod reader;
+mod sqlite;
+mod writer;
+mod xlsx;
+
+pub use reader::read_records;
+
+pub trait RecordWrite {
+    // 预先给出列名，没有任何记录时 sql/sqlite 输出也能按列名建表
+    fn set_headers(&mut self, _headers: &[String]) {}
+    fn write_record(&mut self, record: &Value) -> Result<()>;
+    fn finish(&mut self) -> Result<()>;
+}
+
+pub const DEFAULT_TOML_ROOT: &str = "rows";
+
+pub const DEFAULT_SQL_TABLE: &str = "data";
+
+pub fn create_record_writer<'a>(
+    format: OutputFormat,
+    writer: Box<dyn Write + 'a>,
+) -> Result<Box<dyn RecordWrite + 'a>> {
+    create_record_writer_with_opts(format, writer, &CsvWriteOpts::default())
+}
+
+// toml_root 为 toml 输出时包裹所有记录的表名，table/dialect/batch_size 用于 sql 输出
+pub fn create_record_writer_with_opts<'a>(
+    format: OutputFormat,
+    writer: Box<dyn Write + 'a>,
+    opts: &CsvWriteOpts,
+) -> Result<Box<dyn RecordWrite + 'a>> {
+    let table = opts.table.as_deref().unwrap_or(DEFAULT_SQL_TABLE);
+    let writer: Box<dyn RecordWrite + 'a> = match format {
+        OutputFormat::Json => Box::new(writer::JsonWriter::new(writer)),
+        OutputFormat::Ndjson => Box::new(writer::NdjsonWriter::new(writer)),
+        OutputFormat::Yaml => Box::new(writer::YamlWriter::new(writer)),
+        OutputFormat::Toml => Box::new(writer::TomlWriter::new(
+            writer,
+            opts.toml_root.as_deref().unwrap_or(DEFAULT_TOML_ROOT),
+        )),
+        OutputFormat::Csv => Box::new(writer::CsvWriter::new(writer)),
+        OutputFormat::Xlsx => Box::new(xlsx::XlsxWriter::new(writer)),
+        OutputFormat::Parquet => Box::new(columnar::ColumnarWriter::new(
+            writer,
+            columnar::ColumnarFormat::Parquet(opts.compression.into()),
+        )),
+        OutputFormat::Arrow => Box::new(columnar::ColumnarWriter::new(
+            writer,
+            columnar::ColumnarFormat::Arrow,
+        )),
+        OutputFormat::Sql => Box::new(writer::SqlWriter::new(
+            writer,
+            opts.dialect,
+            table,
+            opts.batch_size,
+        )),
+        // sqlite 数据库只能通过 open_record_writer 写入文件
+        OutputFormat::Sqlite => bail!("SQLite output is not supported by this command"),
+    };
+    Ok(writer)
+}
+
+// sqlite 输出直接写入 output 指定的数据库文件，其余格式写入文件或 stdout
+pub fn open_record_writer(
+    format: OutputFormat,
+    output: &str,
+    opts: &CsvWriteOpts,
+) -> Result<Box<dyn RecordWrite>> {
+    if let OutputFormat::Sqlite = format {
+        let table = opts.table.as_deref().unwrap_or(DEFAULT_SQL_TABLE);
+        let writer = sqlite::SqliteWriter::open(output, table, opts.batch_size)?;
+        return Ok(Box::new(writer));
+    }
+    let writer = data_to_output(output)?;
+    create_record_writer_with_opts(format, writer, opts)
+}
+
+// 根据文件扩展名选择 json/yaml/toml 解析配置文件
+pub fn load_document<T: DeserializeOwned>(path: &str) -> Result<T> {
+    let content = std::fs::read_to_string(path)?;
+    let ext = Path::new(path)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_lowercase();
+    let document = match ext.as_str() {
+        "json" => serde_json::from_str(&content)?,
+        "yaml" | "yml" => serde_yaml::from_str(&content)?,
+        "toml" => toml::from_str(&content)?,
+        v => anyhow::bail!("Unsupported document format: {:?}", v),
+    };
+    Ok(document)
+}
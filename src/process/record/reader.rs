@@ -0,0 +1,114 @@
+use crate::cli::OutputFormat;
+use anyhow::Result;
+use serde_json::Value;
+use std::io::{BufRead, BufReader, Read};
+
+// root 指定记录所在的字段（如 toml 的 [[rows]]），为 None 时自动识别
+pub fn read_records(
+    format: OutputFormat,
+    reader: impl Read,
+    root: Option<&str>,
+) -> Result<Vec<Value>> {
+    if let OutputFormat::Sql
+    | OutputFormat::Sqlite
+    | OutputFormat::Xlsx
+    | OutputFormat::Parquet
+    | OutputFormat::Arrow = format
+    {
+        anyhow::bail!("Unsupported input format: {}", format);
+    }
+    let mut reader = BufReader::new(reader);
+    if let OutputFormat::Ndjson = format {
+        let mut records = Vec::new();
+        for line in reader.lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            records.push(serde_json::from_str(&line)?);
+        }
+        return Ok(records);
+    }
+    if let OutputFormat::Csv = format {
+        let mut reader = csv::Reader::from_reader(reader);
+        let headers = reader.headers()?.clone();
+        let mut records = Vec::new();
+        for record in reader.records() {
+            let record = record?;
+            records.push(headers.iter().zip(record.iter()).collect());
+        }
+        return Ok(records);
+    }
+
+    let mut content = String::new();
+    reader.read_to_string(&mut content)?;
+    let document: Value = match format {
+        OutputFormat::Json | OutputFormat::Ndjson => serde_json::from_str(&content)?,
+        OutputFormat::Yaml => serde_yaml::from_str(&content)?,
+        OutputFormat::Toml => toml::from_str(&content)?,
+        OutputFormat::Csv
+        | OutputFormat::Sql
+        | OutputFormat::Sqlite
+        | OutputFormat::Xlsx
+        | OutputFormat::Parquet
+        | OutputFormat::Arrow => {
+            unreachable!("handled above")
+        }
+    };
+    let document = match (root, document) {
+        (Some(root), Value::Object(mut map)) => map
+            .remove(root)
+            .ok_or_else(|| anyhow::anyhow!("Root key {:?} not found in document", root))?,
+        (_, document) => document,
+    };
+    Ok(document_records(document))
+}
+
+// 顶层数组直接作为记录；只有一个数组字段的对象（如 toml 的 [[rows]]）取该数组；其余视为单条记录
+fn document_records(document: Value) -> Vec<Value> {
+    match document {
+        Value::Array(records) => records,
+        Value::Object(mut map) if map.len() == 1 && map.values().all(Value::is_array) => {
+            match map.values_mut().next().map(Value::take) {
+                Some(Value::Array(records)) => records,
+                _ => unreachable!("object holds exactly one array"),
+            }
+        }
+        Value::Null => Vec::new(),
+        v => vec![v],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_read_records() {
+        let records =
+            read_records(OutputFormat::Json, r#"[{"a":1},{"a":2}]"#.as_bytes(), None).unwrap();
+        assert_eq!(records, vec![json!({"a": 1}), json!({"a": 2})]);
+
+        let records = read_records(
+            OutputFormat::Ndjson,
+            "{\"a\":1}\n\n{\"a\":2}\n".as_bytes(),
+            None,
+        )
+        .unwrap();
+        assert_eq!(records, vec![json!({"a": 1}), json!({"a": 2})]);
+
+        let records =
+            read_records(OutputFormat::Yaml, "- a: 1\n- a: 2\n".as_bytes(), None).unwrap();
+        assert_eq!(records, vec![json!({"a": 1}), json!({"a": 2})]);
+
+        let toml = "[[rows]]\na = 1\n\n[[rows]]\na = 2\n";
+        let records = read_records(OutputFormat::Toml, toml.as_bytes(), None).unwrap();
+        assert_eq!(records, vec![json!({"a": 1}), json!({"a": 2})]);
+
+        let toml = "title = \"x\"\n\n[[players]]\na = 1\n";
+        let records = read_records(OutputFormat::Toml, toml.as_bytes(), Some("players")).unwrap();
+        assert_eq!(records, vec![json!({"a": 1})]);
+        assert!(read_records(OutputFormat::Toml, toml.as_bytes(), Some("rows")).is_err());
+    }
+}
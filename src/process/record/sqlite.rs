@@ -0,0 +1,156 @@
+use super::super::sql::{create_table, insert_prefix, record_columns};
+use super::writer::{record_headers, record_row};
+use super::RecordWrite;
+use crate::cli::SqlDialect;
+use anyhow::Result;
+use rusqlite::types::Value as SqlValue;
+use rusqlite::{params_from_iter, Connection};
+use serde_json::Value;
+
+// 直接写入 sqlite 数据库文件，表不存在时按列名和第一批记录的列类型创建，已存在时追加
+pub struct SqliteWriter {
+    conn: Connection,
+    table: String,
+    batch_size: usize,
+    headers: Option<Vec<String>>,
+    rows: Vec<Vec<Value>>,
+    insert: Option<String>,
+}
+
+impl SqliteWriter {
+    pub fn open(path: &str, table: &str, batch_size: usize) -> Result<Self> {
+        if path == "-" {
+            anyhow::bail!("SQLite output requires a database file path");
+        }
+        Ok(Self::new(Connection::open(path)?, table, batch_size))
+    }
+
+    fn new(conn: Connection, table: &str, batch_size: usize) -> Self {
+        SqliteWriter {
+            conn,
+            table: table.to_string(),
+            batch_size: batch_size.max(1),
+            headers: None,
+            rows: Vec::new(),
+            insert: None,
+        }
+    }
+
+    // 没有记录时按 set_headers 给出的列名建表，所有列都是 TEXT
+    fn write_batch(&mut self) -> Result<()> {
+        let Some(headers) = &self.headers else {
+            return Ok(());
+        };
+        let insert = match &self.insert {
+            Some(insert) => insert,
+            None => {
+                let columns = record_columns(headers, &self.rows);
+                let ddl = create_table(SqlDialect::Sqlite, &self.table, &columns, true);
+                self.conn.execute_batch(&ddl)?;
+                self.conn.execute_batch("BEGIN")?;
+                let placeholders = vec!["?"; headers.len()].join(", ");
+                let insert = format!(
+                    "{} VALUES ({})",
+                    insert_prefix(&self.table, headers),
+                    placeholders
+                );
+                self.insert.insert(insert)
+            }
+        };
+        if self.rows.is_empty() {
+            return Ok(());
+        }
+        let mut statement = self.conn.prepare_cached(insert)?;
+        for row in self.rows.drain(..) {
+            statement.execute(params_from_iter(row.iter().map(sql_value)))?;
+        }
+        Ok(())
+    }
+}
+
+fn sql_value(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(v) => SqlValue::Integer(*v as i64),
+        Value::Number(v) => match v.as_i64() {
+            Some(v) => SqlValue::Integer(v),
+            None => SqlValue::Real(v.as_f64().unwrap_or_default()),
+        },
+        Value::String(v) => SqlValue::Text(v.clone()),
+        v => SqlValue::Text(v.to_string()),
+    }
+}
+
+impl RecordWrite for SqliteWriter {
+    fn set_headers(&mut self, headers: &[String]) {
+        self.headers.get_or_insert_with(|| headers.to_vec());
+    }
+
+    fn write_record(&mut self, record: &Value) -> Result<()> {
+        let headers = self.headers.get_or_insert_with(|| record_headers(record));
+        let row = record_row(headers, record)?;
+        self.rows.push(row);
+        if self.rows.len() >= self.batch_size {
+            self.write_batch()?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.write_batch()?;
+        if self.insert.is_some() {
+            self.conn.execute_batch("COMMIT")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_sqlite_writer() {
+        let mut writer = SqliteWriter::new(Connection::open_in_memory().unwrap(), "t", 2);
+        let records = [
+            json!({"id": 1, "name": "a", "score": 1.5}),
+            json!({"id": 2, "name": null, "score": 2}),
+            json!({"id": 3, "name": "c"}),
+        ];
+        for record in &records {
+            writer.write_record(record).unwrap();
+        }
+        writer.finish().unwrap();
+
+        let conn = &writer.conn;
+        let count: i64 = conn
+            .query_row("SELECT count(*) FROM t WHERE name IS NOT NULL", [], |r| {
+                r.get(0)
+            })
+            .unwrap();
+        assert_eq!(count, 2);
+        let sum: f64 = conn
+            .query_row("SELECT sum(id) + sum(score) FROM t", [], |r| r.get(0))
+            .unwrap();
+        assert_eq!(sum, 9.5);
+        let ddl: String = conn
+            .query_row("SELECT sql FROM sqlite_master WHERE name = 't'", [], |r| {
+                r.get(0)
+            })
+            .unwrap();
+        assert!(ddl.contains("\"score\" REAL"));
+
+        // 只有表头没有数据时也要建表
+        let mut writer = SqliteWriter::new(Connection::open_in_memory().unwrap(), "h", 2);
+        writer.set_headers(&["a".to_string(), "b".to_string()]);
+        writer.finish().unwrap();
+        let ddl: String = writer
+            .conn
+            .query_row("SELECT sql FROM sqlite_master WHERE name = 'h'", [], |r| {
+                r.get(0)
+            })
+            .unwrap();
+        assert!(ddl.contains("\"a\" TEXT") && ddl.contains("\"b\" TEXT"));
+    }
+}
@@ -0,0 +1,464 @@
+use super::super::sql::{create_table, insert_prefix, record_columns, sql_literal};
+use super::RecordWrite;
+use crate::cli::SqlDialect;
+use anyhow::{bail, Result};
+use serde_json::Value;
+use std::io::Write;
+
+pub struct JsonWriter<'a> {
+    writer: Box<dyn Write + 'a>,
+    count: usize,
+}
+
+pub struct NdjsonWriter<'a> {
+    writer: Box<dyn Write + 'a>,
+}
+
+pub struct YamlWriter<'a> {
+    writer: Box<dyn Write + 'a>,
+    count: usize,
+}
+
+// toml 不支持顶层数组，每条记录输出为 root 下的一个 [[root]] 表
+pub struct TomlWriter<'a> {
+    writer: Box<dyn Write + 'a>,
+    root: String,
+    count: usize,
+}
+
+// 表头取自 set_headers 或第一条记录的字段，后续记录按该表头输出
+pub struct CsvWriter<'a> {
+    writer: csv::Writer<Box<dyn Write + 'a>>,
+    headers: Option<Vec<String>>,
+    header_written: bool,
+}
+
+// 先缓存一批记录用于确定列类型，之后每 batch_size 条输出一条 INSERT，整个脚本在一个事务中
+pub struct SqlWriter<'a> {
+    writer: Box<dyn Write + 'a>,
+    dialect: SqlDialect,
+    table: String,
+    batch_size: usize,
+    headers: Option<Vec<String>>,
+    rows: Vec<Vec<Value>>,
+    created: bool,
+}
+
+impl<'a> JsonWriter<'a> {
+    pub fn new(writer: Box<dyn Write + 'a>) -> Self {
+        JsonWriter { writer, count: 0 }
+    }
+}
+
+impl<'a> NdjsonWriter<'a> {
+    pub fn new(writer: Box<dyn Write + 'a>) -> Self {
+        NdjsonWriter { writer }
+    }
+}
+
+impl<'a> YamlWriter<'a> {
+    pub fn new(writer: Box<dyn Write + 'a>) -> Self {
+        YamlWriter { writer, count: 0 }
+    }
+}
+
+impl<'a> TomlWriter<'a> {
+    pub fn new(writer: Box<dyn Write + 'a>, root: &str) -> Self {
+        TomlWriter {
+            writer,
+            root: root.to_string(),
+            count: 0,
+        }
+    }
+}
+
+impl<'a> CsvWriter<'a> {
+    pub fn new(writer: Box<dyn Write + 'a>) -> Self {
+        CsvWriter {
+            writer: csv::Writer::from_writer(writer),
+            headers: None,
+            header_written: false,
+        }
+    }
+}
+
+impl<'a> SqlWriter<'a> {
+    pub fn new(
+        writer: Box<dyn Write + 'a>,
+        dialect: SqlDialect,
+        table: &str,
+        batch_size: usize,
+    ) -> Self {
+        SqlWriter {
+            writer,
+            dialect,
+            table: table.to_string(),
+            batch_size: batch_size.max(1),
+            headers: None,
+            rows: Vec::new(),
+            created: false,
+        }
+    }
+
+    // 第一次写入时按已缓存的记录推断列类型建表，没有记录时所有列都是 TEXT
+    fn write_batch(&mut self) -> Result<()> {
+        let Some(headers) = &self.headers else {
+            return Ok(());
+        };
+        if !self.created {
+            let columns = record_columns(headers, &self.rows);
+            self.writer.write_all(b"BEGIN;\n")?;
+            let ddl = create_table(self.dialect, &self.table, &columns, true);
+            self.writer.write_all(ddl.as_bytes())?;
+            self.created = true;
+        }
+        if self.rows.is_empty() {
+            return Ok(());
+        }
+        let values = self
+            .rows
+            .drain(..)
+            .map(|row| {
+                let row = row
+                    .iter()
+                    .map(|v| sql_literal(self.dialect, v))
+                    .collect::<Vec<_>>();
+                format!("    ({})", row.join(", "))
+            })
+            .collect::<Vec<_>>();
+        let statement = format!(
+            "{} VALUES\n{};\n",
+            insert_prefix(&self.table, headers),
+            values.join(",\n")
+        );
+        self.writer.write_all(statement.as_bytes())?;
+        Ok(())
+    }
+}
+
+impl RecordWrite for JsonWriter<'_> {
+    fn write_record(&mut self, record: &Value) -> Result<()> {
+        let sep = if self.count == 0 { "[" } else { "," };
+        self.writer.write_all(sep.as_bytes())?;
+        serde_json::to_writer(&mut self.writer, record)?;
+        self.count += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        if self.count == 0 {
+            self.writer.write_all(b"[")?;
+        }
+        self.writer.write_all(b"]")?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+impl RecordWrite for NdjsonWriter<'_> {
+    fn write_record(&mut self, record: &Value) -> Result<()> {
+        serde_json::to_writer(&mut self.writer, record)?;
+        self.writer.write_all(b"\n")?;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+impl RecordWrite for YamlWriter<'_> {
+    fn write_record(&mut self, record: &Value) -> Result<()> {
+        // 单元素序列的 yaml 输出可以直接拼接成一个完整的序列
+        let content = serde_yaml::to_string(&[record])?;
+        self.writer.write_all(content.as_bytes())?;
+        self.count += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        if self.count == 0 {
+            self.writer.write_all(b"[]\n")?;
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+impl RecordWrite for TomlWriter<'_> {
+    fn write_record(&mut self, record: &Value) -> Result<()> {
+        let record = match strip_nulls(record.clone()) {
+            v @ Value::Object(_) => v,
+            v => serde_json::json!({ "value": v }),
+        };
+        let mut table = serde_json::Map::new();
+        table.insert(self.root.clone(), Value::Array(vec![record]));
+        let content = toml::to_string(&table)?;
+        if self.count > 0 {
+            self.writer.write_all(b"\n")?;
+        }
+        self.writer.write_all(content.as_bytes())?;
+        self.count += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        if self.count == 0 {
+            let mut table = serde_json::Map::new();
+            table.insert(self.root.clone(), Value::Array(Vec::new()));
+            let content = toml::to_string(&table)?;
+            self.writer.write_all(content.as_bytes())?;
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+// toml 没有 null，直接省略这些字段
+fn strip_nulls(value: Value) -> Value {
+    match value {
+        Value::Object(map) => Value::Object(
+            map.into_iter()
+                .filter(|(_, v)| !v.is_null())
+                .map(|(k, v)| (k, strip_nulls(v)))
+                .collect(),
+        ),
+        Value::Array(items) => Value::Array(
+            items
+                .into_iter()
+                .filter(|v| !v.is_null())
+                .map(strip_nulls)
+                .collect(),
+        ),
+        v => v,
+    }
+}
+
+impl CsvWriter<'_> {
+    fn write_header(&mut self) -> Result<()> {
+        if let (Some(headers), false) = (&self.headers, self.header_written) {
+            self.writer.write_record(headers)?;
+            self.header_written = true;
+        }
+        Ok(())
+    }
+}
+
+impl RecordWrite for CsvWriter<'_> {
+    fn set_headers(&mut self, headers: &[String]) {
+        self.headers.get_or_insert_with(|| headers.to_vec());
+    }
+
+    fn write_record(&mut self, record: &Value) -> Result<()> {
+        let headers = self.headers.get_or_insert_with(|| record_headers(record));
+        let row = record_row(headers, record)?
+            .iter()
+            .map(cell_text)
+            .collect::<Vec<_>>();
+        self.write_header()?;
+        self.writer.write_record(row)?;
+        Ok(())
+    }
+
+    // 只有表头没有记录时也输出表头
+    fn finish(&mut self) -> Result<()> {
+        self.write_header()?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+impl RecordWrite for SqlWriter<'_> {
+    fn set_headers(&mut self, headers: &[String]) {
+        self.headers.get_or_insert_with(|| headers.to_vec());
+    }
+
+    fn write_record(&mut self, record: &Value) -> Result<()> {
+        let headers = self.headers.get_or_insert_with(|| record_headers(record));
+        let row = record_row(headers, record)?;
+        self.rows.push(row);
+        if self.rows.len() >= self.batch_size {
+            self.write_batch()?;
+        }
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.write_batch()?;
+        if self.created {
+            self.writer.write_all(b"COMMIT;\n")?;
+        }
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+// 表格类输出的列名取自第一条记录的字段，非对象记录视为单列 value
+pub fn record_headers(record: &Value) -> Vec<String> {
+    match record {
+        Value::Object(map) => map.keys().cloned().collect(),
+        _ => vec!["value".to_string()],
+    }
+}
+
+// 表头中缺少的字段为空值；出现表头以外的字段时报错，而不是丢弃这些数据
+pub fn record_row(headers: &[String], record: &Value) -> Result<Vec<Value>> {
+    match record {
+        Value::Object(map) => {
+            if let Some(key) = map.keys().find(|key| !headers.contains(key)) {
+                bail!(
+                    "Field {:?} is not one of the output columns {:?}",
+                    key,
+                    headers
+                );
+            }
+            Ok(headers
+                .iter()
+                .map(|key| map.get(key).cloned().unwrap_or(Value::Null))
+                .collect())
+        }
+        v => Ok(vec![v.clone()]),
+    }
+}
+
+fn cell_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::String(s) => s.clone(),
+        v => v.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn write_all(writer: &mut dyn RecordWrite, records: &[Value]) {
+        for record in records {
+            writer.write_record(record).unwrap();
+        }
+        writer.finish().unwrap();
+    }
+
+    #[test]
+    fn test_json_writer() {
+        let records = vec![json!({"a": "1"}), json!({"a": "2"})];
+        let mut buf = Vec::new();
+        write_all(&mut JsonWriter::new(Box::new(&mut buf)), &records);
+        assert_eq!(String::from_utf8(buf).unwrap(), r#"[{"a":"1"},{"a":"2"}]"#);
+
+        let mut buf = Vec::new();
+        write_all(&mut JsonWriter::new(Box::new(&mut buf)), &[]);
+        assert_eq!(String::from_utf8(buf).unwrap(), "[]");
+    }
+
+    #[test]
+    fn test_ndjson_writer() {
+        let records = vec![json!({"a": "1"}), json!({"a": "2"})];
+        let mut buf = Vec::new();
+        write_all(&mut NdjsonWriter::new(Box::new(&mut buf)), &records);
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "{\"a\":\"1\"}\n{\"a\":\"2\"}\n"
+        );
+    }
+
+    #[test]
+    fn test_csv_writer() {
+        let records = vec![json!({"b": "x,y", "a": 1}), json!({"a": 2})];
+        let mut buf = Vec::new();
+        write_all(&mut CsvWriter::new(Box::new(&mut buf)), &records);
+        assert_eq!(String::from_utf8(buf).unwrap(), "b,a\n\"x,y\",1\n,2\n");
+
+        // 后续记录出现新字段时报错，不能静默丢弃
+        let mut buf = Vec::new();
+        let mut writer = CsvWriter::new(Box::new(&mut buf));
+        writer.write_record(&json!({"a": 1})).unwrap();
+        let err = writer
+            .write_record(&json!({"a": 2, "c": true}))
+            .unwrap_err();
+        assert!(err.to_string().starts_with("Field \"c\""));
+
+        // 预先给出表头时，没有记录也输出表头
+        let mut buf = Vec::new();
+        let mut writer = CsvWriter::new(Box::new(&mut buf));
+        writer.set_headers(&["a".to_string(), "b".to_string()]);
+        writer.finish().unwrap();
+        drop(writer);
+        assert_eq!(String::from_utf8(buf).unwrap(), "a,b\n");
+    }
+
+    #[test]
+    fn test_sql_writer() {
+        let records = vec![
+            json!({"id": 1, "name": "o'neil", "ok": true}),
+            json!({"id": 2, "name": null, "ok": false}),
+            json!({"id": 3, "name": "c", "ok": null}),
+        ];
+        let mut buf = Vec::new();
+        write_all(
+            &mut SqlWriter::new(Box::new(&mut buf), SqlDialect::Postgres, "t", 2),
+            &records,
+        );
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "BEGIN;\nCREATE TABLE IF NOT EXISTS \"t\" (\n    \"id\" BIGINT,\n    \"name\" TEXT,\n    \"ok\" BOOLEAN\n);\n\
+             INSERT INTO \"t\" (\"id\", \"name\", \"ok\") VALUES\n    (1, 'o''neil', TRUE),\n    (2, NULL, FALSE);\n\
+             INSERT INTO \"t\" (\"id\", \"name\", \"ok\") VALUES\n    (3, 'c', NULL);\nCOMMIT;\n"
+        );
+
+        let mut buf = Vec::new();
+        write_all(
+            &mut SqlWriter::new(Box::new(&mut buf), SqlDialect::Sqlite, "t", 2),
+            &[],
+        );
+        assert!(buf.is_empty());
+
+        // 只有表头没有数据时按列名建表，列类型为 TEXT
+        let mut buf = Vec::new();
+        let mut writer = SqlWriter::new(Box::new(&mut buf), SqlDialect::Sqlite, "t", 2);
+        writer.set_headers(&["a".to_string(), "b".to_string()]);
+        write_all(&mut writer, &[]);
+        drop(writer);
+        assert_eq!(
+            String::from_utf8(buf).unwrap(),
+            "BEGIN;\nCREATE TABLE IF NOT EXISTS \"t\" (\n    \"a\" TEXT,\n    \"b\" TEXT\n);\nCOMMIT;\n"
+        );
+    }
+
+    #[test]
+    fn test_toml_writer() {
+        let records = vec![
+            json!({"Name": "a", "Kit Number": 1, "note": null}),
+            json!({"Name": "b", "Kit Number": 2, "ok": true}),
+        ];
+        let mut buf = Vec::new();
+        write_all(
+            &mut TomlWriter::new(Box::new(&mut buf), "players"),
+            &records,
+        );
+        let content = String::from_utf8(buf).unwrap();
+        assert!(content.starts_with("[[players]]\n"));
+        let parsed: toml::Table = toml::from_str(&content).unwrap();
+        let players = parsed["players"].as_array().unwrap();
+        assert_eq!(players.len(), 2);
+        assert_eq!(players[0]["Kit Number"].as_integer(), Some(1));
+        assert!(players[0].get("note").is_none());
+
+        let mut buf = Vec::new();
+        write_all(&mut TomlWriter::new(Box::new(&mut buf), "rows"), &[]);
+        assert_eq!(String::from_utf8(buf).unwrap(), "rows = []\n");
+    }
+
+    #[test]
+    fn test_yaml_writer() {
+        let records = vec![json!({"a": "1", "b": "x"}), json!({"a": "2", "b": "y"})];
+        let mut buf = Vec::new();
+        write_all(&mut YamlWriter::new(Box::new(&mut buf)), &records);
+        let content = String::from_utf8(buf).unwrap();
+        let parsed: Vec<Value> = serde_yaml::from_str(&content).unwrap();
+        assert_eq!(parsed, records);
+    }
+}
@@ -0,0 +1,133 @@
+use super::writer::{record_headers, record_row};
+use super::RecordWrite;
+use anyhow::Result;
+use rust_xlsxwriter::{Color, Format, FormatBorder, Workbook, Worksheet};
+use serde_json::Value;
+use std::io::Write;
+
+// xlsx 是 zip 包，只能在内存中生成整个工作簿后一次性写出
+pub struct XlsxWriter<'a> {
+    writer: Box<dyn Write + 'a>,
+    worksheet: Worksheet,
+    headers: Option<Vec<String>>,
+    row: u32,
+}
+
+impl<'a> XlsxWriter<'a> {
+    pub fn new(writer: Box<dyn Write + 'a>) -> Self {
+        XlsxWriter {
+            writer,
+            worksheet: Worksheet::new(),
+            headers: None,
+            row: 0,
+        }
+    }
+
+    // 表头在第一条记录或 finish 时写入，没有记录时也输出表头
+    fn write_header(&mut self) -> Result<()> {
+        let Some(headers) = &self.headers else {
+            return Ok(());
+        };
+        if self.row > 0 {
+            return Ok(());
+        }
+        let format = Format::new()
+            .set_bold()
+            .set_background_color(Color::RGB(0xD9E1F2))
+            .set_border_bottom(FormatBorder::Thin);
+        for (col, name) in headers.iter().enumerate() {
+            self.worksheet
+                .write_string_with_format(0, col as u16, name, &format)?;
+        }
+        self.worksheet.set_freeze_panes(1, 0)?;
+        self.row = 1;
+        Ok(())
+    }
+}
+
+impl RecordWrite for XlsxWriter<'_> {
+    fn set_headers(&mut self, headers: &[String]) {
+        self.headers.get_or_insert_with(|| headers.to_vec());
+    }
+
+    fn write_record(&mut self, record: &Value) -> Result<()> {
+        let headers = self.headers.get_or_insert_with(|| record_headers(record));
+        let row = record_row(headers, record)?;
+        self.write_header()?;
+        for (col, value) in row.iter().enumerate() {
+            let col = col as u16;
+            match value {
+                Value::Null => {}
+                Value::Bool(v) => {
+                    self.worksheet.write_boolean(self.row, col, *v)?;
+                }
+                Value::Number(v) => {
+                    self.worksheet
+                        .write_number(self.row, col, v.as_f64().unwrap_or_default())?;
+                }
+                Value::String(v) => {
+                    self.worksheet.write_string(self.row, col, v)?;
+                }
+                v => {
+                    self.worksheet.write_string(self.row, col, v.to_string())?;
+                }
+            }
+        }
+        self.row += 1;
+        Ok(())
+    }
+
+    fn finish(&mut self) -> Result<()> {
+        self.write_header()?;
+        let mut worksheet = std::mem::replace(&mut self.worksheet, Worksheet::new());
+        worksheet.autofit();
+        let mut workbook = Workbook::new();
+        workbook.push_worksheet(worksheet);
+        self.writer.write_all(&workbook.save_to_buffer()?)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use calamine::{Data, Reader, Xlsx};
+    use serde_json::json;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_xlsx_writer() {
+        let mut buf = Vec::new();
+        let mut writer = XlsxWriter::new(Box::new(&mut buf));
+        writer
+            .write_record(&json!({"name": "a", "score": 1.5, "ok": true}))
+            .unwrap();
+        writer
+            .write_record(&json!({"name": "b", "score": null, "ok": false}))
+            .unwrap();
+        writer.finish().unwrap();
+        drop(writer);
+
+        let mut workbook = Xlsx::new(Cursor::new(buf)).unwrap();
+        let range = workbook.worksheet_range("Sheet1").unwrap();
+        assert_eq!(range.get_size(), (3, 3));
+        assert_eq!(range.get_value((0, 1)), Some(&Data::String("score".into())));
+        assert_eq!(range.get_value((1, 1)), Some(&Data::Float(1.5)));
+        assert_eq!(range.get_value((2, 2)), Some(&Data::Bool(false)));
+    }
+
+    #[test]
+    fn test_xlsx_writer_header_only() {
+        let mut buf = Vec::new();
+        let mut writer = XlsxWriter::new(Box::new(&mut buf));
+        writer.set_headers(&["a".to_string(), "b".to_string()]);
+        writer.finish().unwrap();
+        drop(writer);
+
+        let mut workbook = Xlsx::new(Cursor::new(buf)).unwrap();
+        let range = workbook.worksheet_range("Sheet1").unwrap();
+        assert_eq!(range.get_size(), (1, 2));
+        assert_eq!(range.get_value((0, 1)), Some(&Data::String("b".into())));
+    }
+}
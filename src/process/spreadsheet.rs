@@ -0,0 +1,88 @@
+use anyhow::{anyhow, Result};
+use calamine::{open_workbook_auto, Data, Range, Reader};
+use std::path::Path;
+
+const SPREADSHEET_EXTENSIONS: [&str; 5] = ["xlsx", "xlsm", "xlsb", "xls", "ods"];
+
+pub fn is_spreadsheet(input: &str) -> bool {
+    Path::new(input)
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .is_some_and(|ext| SPREADSHEET_EXTENSIONS.contains(&ext.to_lowercase().as_str()))
+}
+
+// 读取工作表并转换为 utf-8 的 csv 数据；sheet 为表名或从 1 开始的序号，默认第一个
+pub fn read_sheet(path: &str, sheet: Option<&str>) -> Result<Vec<u8>> {
+    let mut workbook = open_workbook_auto(path)?;
+    let names = workbook.sheet_names();
+    let name = match sheet {
+        None => names.first(),
+        Some(sheet) => names.iter().find(|n| *n == sheet).or_else(|| {
+            let index = sheet.parse::<usize>().ok()?;
+            names.get(index.checked_sub(1)?)
+        }),
+    }
+    .ok_or_else(|| {
+        let sheet = sheet.unwrap_or("1");
+        anyhow!("Sheet {:?} not found, available: {:?}", sheet, names)
+    })?
+    .clone();
+    let range = workbook.worksheet_range(&name)?;
+    range_to_csv(&range)
+}
+
+fn range_to_csv(range: &Range<Data>) -> Result<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(Vec::new());
+    for row in range.rows() {
+        writer.write_record(row.iter().map(cell_text))?;
+    }
+    Ok(writer.into_inner()?)
+}
+
+fn cell_text(cell: &Data) -> String {
+    match cell {
+        Data::Empty => String::new(),
+        Data::String(v) | Data::DateTimeIso(v) | Data::DurationIso(v) => v.clone(),
+        Data::Int(v) => v.to_string(),
+        // 整数值的浮点单元格不输出小数部分，保证后续能推断为 integer
+        Data::Float(v) if v.fract() == 0.0 && v.abs() < 1e15 => (*v as i64).to_string(),
+        Data::Float(v) => v.to_string(),
+        Data::Bool(v) => v.to_string(),
+        Data::DateTime(v) if !v.is_duration() => match v.as_datetime() {
+            Some(dt) if dt.time() == chrono::NaiveTime::MIN => dt.format("%Y-%m-%d").to_string(),
+            Some(dt) => dt.format("%Y-%m-%dT%H:%M:%S").to_string(),
+            None => v.as_f64().to_string(),
+        },
+        Data::DateTime(v) => v.as_f64().to_string(),
+        Data::Error(e) => e.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_spreadsheet() {
+        assert!(is_spreadsheet("data/report.XLSX"));
+        assert!(is_spreadsheet("a.ods"));
+        assert!(!is_spreadsheet("assets/juventus.csv"));
+        assert!(!is_spreadsheet("-"));
+    }
+
+    #[test]
+    fn test_range_to_csv() {
+        let mut range = Range::new((0, 0), (2, 2));
+        range.set_value((0, 0), Data::String("name".to_string()));
+        range.set_value((0, 1), Data::String("score".to_string()));
+        range.set_value((0, 2), Data::String("ok".to_string()));
+        range.set_value((1, 0), Data::String("a,b".to_string()));
+        range.set_value((1, 1), Data::Float(3.0));
+        range.set_value((1, 2), Data::Bool(true));
+        range.set_value((2, 1), Data::Float(2.5));
+        assert_eq!(
+            String::from_utf8(range_to_csv(&range).unwrap()).unwrap(),
+            "name,score,ok\n\"a,b\",3,true\n,2.5,\n"
+        );
+    }
+}
@@ -0,0 +1,141 @@
+use super::record::infer::ColumnType;
+use crate::cli::SqlDialect;
+use serde_json::Value;
+use std::fmt::Write as _;
+use std::path::Path;
+
+pub struct SqlColumn {
+    pub name: String,
+    pub column_type: ColumnType,
+    pub nullable: bool,
+    // 日期列中出现了带时间的值
+    pub timestamp: bool,
+}
+
+// 两种方言都支持双引号标识符，内部的双引号需要写两次
+pub fn quote_ident(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+pub fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+pub fn sql_literal(dialect: SqlDialect, value: &Value) -> String {
+    match (dialect, value) {
+        (_, Value::Null) => "NULL".to_string(),
+        (SqlDialect::Sqlite, Value::Bool(v)) => (*v as u8).to_string(),
+        (SqlDialect::Postgres, Value::Bool(v)) => v.to_string().to_uppercase(),
+        (_, Value::Number(v)) => v.to_string(),
+        (_, Value::String(v)) => quote_literal(v),
+        (_, v) => quote_literal(&v.to_string()),
+    }
+}
+
+// 表名默认取输入文件名（不含扩展名），stdin 时为 data
+pub fn default_table_name(input: &str) -> String {
+    Path::new(input)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .filter(|_| input != "-")
+        .unwrap_or("data")
+        .to_string()
+}
+
+// 根据已转换记录中的值确定列类型，未开启 --infer 时所有列都是 TEXT
+pub fn record_columns(names: &[String], rows: &[Vec<Value>]) -> Vec<SqlColumn> {
+    names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| {
+            let column_type = rows
+                .iter()
+                .map(|row| match &row[i] {
+                    Value::Null => ColumnType::Null,
+                    Value::Bool(_) => ColumnType::Bool,
+                    Value::Number(v) if v.is_i64() => ColumnType::Integer,
+                    Value::Number(_) => ColumnType::Float,
+                    _ => ColumnType::String,
+                })
+                .fold(ColumnType::Null, ColumnType::merge);
+            SqlColumn {
+                name: name.clone(),
+                column_type,
+                nullable: true,
+                timestamp: false,
+            }
+        })
+        .collect()
+}
+
+pub fn insert_prefix(table: &str, names: &[String]) -> String {
+    let names = names
+        .iter()
+        .map(|name| quote_ident(name))
+        .collect::<Vec<_>>()
+        .join(", ");
+    format!("INSERT INTO {} ({})", quote_ident(table), names)
+}
+
+pub fn sql_type(dialect: SqlDialect, column: &SqlColumn) -> &'static str {
+    match (dialect, column.column_type) {
+        (SqlDialect::Sqlite, ColumnType::Integer | ColumnType::Bool) => "INTEGER",
+        (SqlDialect::Sqlite, ColumnType::Float) => "REAL",
+        (SqlDialect::Sqlite, _) => "TEXT",
+        (SqlDialect::Postgres, ColumnType::Integer) => "BIGINT",
+        (SqlDialect::Postgres, ColumnType::Float) => "DOUBLE PRECISION",
+        (SqlDialect::Postgres, ColumnType::Bool) => "BOOLEAN",
+        (SqlDialect::Postgres, ColumnType::Date) if column.timestamp => "TIMESTAMPTZ",
+        (SqlDialect::Postgres, ColumnType::Date) => "DATE",
+        (SqlDialect::Postgres, _) => "TEXT",
+    }
+}
+
+pub fn create_table(
+    dialect: SqlDialect,
+    table: &str,
+    columns: &[SqlColumn],
+    if_not_exists: bool,
+) -> String {
+    let mut out = format!(
+        "CREATE TABLE {}{} (\n",
+        if if_not_exists { "IF NOT EXISTS " } else { "" },
+        quote_ident(table)
+    );
+    for (i, column) in columns.iter().enumerate() {
+        let _ = write!(
+            out,
+            "    {} {}",
+            quote_ident(&column.name),
+            sql_type(dialect, column)
+        );
+        if !column.nullable {
+            out.push_str(" NOT NULL");
+        }
+        out.push_str(if i + 1 < columns.len() { ",\n" } else { "\n" });
+    }
+    out.push_str(");\n");
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_quote() {
+        assert_eq!(quote_ident("a\"b"), "\"a\"\"b\"");
+        assert_eq!(quote_literal("it's"), "'it''s'");
+        assert_eq!(sql_literal(SqlDialect::Sqlite, &json!(true)), "1");
+        assert_eq!(sql_literal(SqlDialect::Postgres, &json!(false)), "FALSE");
+        assert_eq!(sql_literal(SqlDialect::Postgres, &json!(null)), "NULL");
+        assert_eq!(sql_literal(SqlDialect::Sqlite, &json!(1.5)), "1.5");
+    }
+
+    #[test]
+    fn test_default_table_name() {
+        assert_eq!(default_table_name("assets/juventus.csv"), "juventus");
+        assert_eq!(default_table_name("-"), "data");
+    }
+}
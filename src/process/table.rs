@@ -0,0 +1,130 @@
+use std::fmt::Write;
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+const MIN_COLUMN_WIDTH: usize = 3;
+const COLUMN_SEPARATOR: &str = " | ";
+
+// 按列宽对齐输出文本表格
+pub fn render_table(headers: &[String], rows: &[Vec<String>]) -> String {
+    render_table_fit(headers, rows, None, None)
+}
+
+// max_width 为整个表格的最大宽度，max_cell_width 为单元格的最大宽度，超出部分截断
+pub fn render_table_fit(
+    headers: &[String],
+    rows: &[Vec<String>],
+    max_width: Option<usize>,
+    max_cell_width: Option<usize>,
+) -> String {
+    let mut widths = headers.iter().map(|h| display_width(h)).collect::<Vec<_>>();
+    for row in rows {
+        for (i, cell) in row.iter().enumerate() {
+            if i < widths.len() {
+                widths[i] = widths[i].max(display_width(cell));
+            }
+        }
+    }
+    if let Some(limit) = max_cell_width {
+        for width in widths.iter_mut() {
+            *width = (*width).min(limit.max(MIN_COLUMN_WIDTH));
+        }
+    }
+    if let Some(limit) = max_width {
+        shrink_widths(&mut widths, limit);
+    }
+
+    let mut out = String::new();
+    render_row(&mut out, headers, &widths);
+    let separator = widths.iter().map(|w| "-".repeat(*w)).collect::<Vec<_>>();
+    render_row(&mut out, &separator, &widths);
+    for row in rows {
+        render_row(&mut out, row, &widths);
+    }
+    out
+}
+
+// 每次缩小最宽的一列，直到总宽度不超过限制或所有列都达到最小宽度
+fn shrink_widths(widths: &mut [usize], limit: usize) {
+    let separators = COLUMN_SEPARATOR.len() * widths.len().saturating_sub(1);
+    while widths.iter().sum::<usize>() + separators > limit {
+        let Some(widest) = widths
+            .iter_mut()
+            .filter(|w| **w > MIN_COLUMN_WIDTH)
+            .max_by_key(|w| **w)
+        else {
+            break;
+        };
+        *widest -= 1;
+    }
+}
+
+fn render_row(out: &mut String, cells: &[String], widths: &[usize]) {
+    let line = widths
+        .iter()
+        .enumerate()
+        .map(|(i, width)| {
+            let cell = cells.get(i).map(String::as_str).unwrap_or_default();
+            let cell = truncate(cell, *width);
+            let pad = width.saturating_sub(display_width(&cell));
+            format!("{}{}", cell, " ".repeat(pad))
+        })
+        .collect::<Vec<_>>()
+        .join(COLUMN_SEPARATOR);
+    let _ = writeln!(out, "{}", line.trim_end());
+}
+
+fn truncate(cell: &str, width: usize) -> String {
+    // 换行符会破坏表格布局，统一替换为空格
+    let cell = cell.replace(['\r', '\n'], " ");
+    if display_width(&cell) <= width {
+        return cell;
+    }
+    let mut out = String::new();
+    let mut used = 0;
+    for c in cell.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w + 1 > width {
+            break;
+        }
+        out.push(c);
+        used += w;
+    }
+    out.push('…');
+    out
+}
+
+pub fn display_width(s: &str) -> usize {
+    s.width()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_table() {
+        let headers = vec!["name".to_string(), "n".to_string()];
+        let rows = vec![
+            vec!["alice".to_string(), "1".to_string()],
+            vec!["bob".to_string(), "22".to_string()],
+        ];
+        assert_eq!(
+            render_table(&headers, &rows),
+            "name  | n\n----- | --\nalice | 1\nbob   | 22\n"
+        );
+    }
+
+    #[test]
+    fn test_render_table_fit() {
+        let headers = vec!["name".to_string(), "city".to_string()];
+        let rows = vec![vec!["Wojciech Szczesny".to_string(), "都灵".to_string()]];
+        assert_eq!(
+            render_table_fit(&headers, &rows, Some(15), None),
+            "name     | city\n-------- | ----\nWojciec… | 都灵\n"
+        );
+        assert_eq!(
+            render_table_fit(&headers, &rows, None, Some(5)),
+            "name  | city\n----- | ----\nWojc… | 都灵\n"
+        );
+    }
+}
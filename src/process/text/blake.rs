@@ -1,5 +1,5 @@
 use super::{KeyGenerate, TextSign, TextVerify};
-use crate::process_gen_pass;
+use crate::{process_gen_pass, PasswordPolicy};
 use base64::engine::general_purpose::URL_SAFE_NO_PAD;
 use base64::Engine;
 use std::collections::HashMap;
@@ -78,7 +78,11 @@ impl TextVerify for BlakeVerify {
 
 impl KeyGenerate for BlakeGenerate {
     fn generate(&self) -> anyhow::Result<HashMap<&'static str, Vec<u8>>> {
-        let keys = process_gen_pass(32, 1, true, true, true, true)?;
+        let policy = PasswordPolicy {
+            length: 32,
+            ..Default::default()
+        };
+        let keys = process_gen_pass(&policy, 1)?;
         let mut key_map = HashMap::new();
         if keys.len() == 1 {
             key_map.insert("blake3.key", keys[0].as_bytes().to_vec());
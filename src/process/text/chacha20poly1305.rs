@@ -33,6 +33,31 @@ impl ChaCha20Poly1305EnDe {
     }
 }
 
+// 单元格加密：每个值使用随机 nonce，输出 base64(nonce || 密文)，可以单独解密
+impl ChaCha20Poly1305EnDe {
+    pub fn encrypt_cell(&self, plain_text: &[u8]) -> Result<String> {
+        let nonce = ChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let cipher_text = self
+            .cipher
+            .encrypt(&nonce, plain_text)
+            .map_err(|_| anyhow!("Encrypt failed"))?;
+        let mut buffer = nonce.to_vec();
+        buffer.extend_from_slice(&cipher_text);
+        Ok(URL_SAFE_NO_PAD.encode(buffer))
+    }
+
+    pub fn decrypt_cell(&self, cell: &str) -> Result<Vec<u8>> {
+        let buffer = URL_SAFE_NO_PAD.decode(cell.trim())?;
+        if buffer.len() < 12 {
+            return Err(anyhow!("Cipher text is too short"));
+        }
+        let (nonce, cipher_text) = buffer.split_at(12);
+        self.cipher
+            .decrypt(Nonce::from_slice(nonce), cipher_text)
+            .map_err(|_| anyhow!("Decrypt failed"))
+    }
+}
+
 impl TextEncrypt for ChaCha20Poly1305EnDe {
     fn encrypt(&self, reader: &mut dyn Read) -> Result<String> {
         let mut buffer = Vec::new();
@@ -137,4 +162,19 @@ mod tests {
         println!("{:?}", String::from_utf8(plain_text.clone()).unwrap());
         assert_eq!(plain_text, "hello world!".as_bytes());
     }
+
+    #[test]
+    fn test_chacha20poly1305_cell() {
+        let generator = ChaCha20Poly1305Generator::new();
+        let keys = generator.generate().unwrap();
+        let en_de =
+            ChaCha20Poly1305EnDe::try_new(keys.get("chacha20poly1305.key").unwrap()).unwrap();
+
+        let first = en_de.encrypt_cell(b"secret").unwrap();
+        let second = en_de.encrypt_cell(b"secret").unwrap();
+        assert_ne!(first, second);
+        assert_eq!(en_de.decrypt_cell(&first).unwrap(), b"secret");
+        assert_eq!(en_de.decrypt_cell(&second).unwrap(), b"secret");
+        assert!(en_de.decrypt_cell("c2hvcnQ").is_err());
+    }
 }
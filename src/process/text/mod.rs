@@ -4,7 +4,7 @@ use std::collections::HashMap;
 use std::io::Read;
 
 mod blake;
-mod chacha20poly1305;
+pub mod chacha20poly1305;
 mod ed25519;
 
 pub trait TextSign {